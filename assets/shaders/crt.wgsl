@@ -15,6 +15,8 @@ struct CrtSettings {
     brightness: f32,
     noise_intensity: f32,
     time: f32,
+    flip: f32,
+    collapse: f32,
 }
 @group(0) @binding(2) var<uniform> settings: CrtSettings;
 
@@ -37,6 +39,13 @@ fn apply_curvature(uv: vec2<f32>, curvature: f32) -> vec2<f32> {
     return centered * 0.5 + 0.5;
 }
 
+// Squash the picture into a horizontal line, then the line into a dot, like a tube switching off
+fn apply_collapse(uv: vec2<f32>, collapse: f32) -> vec2<f32> {
+    let squash_y = max(1.0 - clamp(collapse * 2.0, 0.0, 1.0), 0.005);
+    let squash_x = max(1.0 - clamp(collapse * 2.0 - 1.0, 0.0, 1.0), 0.005);
+    return (uv - 0.5) / vec2<f32>(squash_x, squash_y) + 0.5;
+}
+
 // Check if UV is outside the screen bounds (for curved edges)
 fn is_outside_screen(uv: vec2<f32>) -> bool {
     return uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0;
@@ -97,8 +106,17 @@ fn rgb_subpixel(uv: vec2<f32>, color: vec3<f32>) -> vec3<f32> {
 
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Flip the picture upside down (used by curses)
+    let flipped_uv = vec2<f32>(in.uv.x, mix(in.uv.y, 1.0 - in.uv.y, settings.flip));
+
+    // Collapse the picture when the player dies
+    if (settings.collapse >= 1.0) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    let uv = apply_collapse(flipped_uv, settings.collapse);
+
     // Apply CRT curvature
-    let curved_uv = apply_curvature(in.uv, settings.curvature);
+    let curved_uv = apply_curvature(uv, settings.curvature);
     
     // Return black if outside screen bounds (creates the curved edge effect)
     if (is_outside_screen(curved_uv)) {
@@ -123,8 +141,8 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     let noise = hash(curved_uv * 1000.0 + vec2<f32>(settings.time * 100.0, 0.0));
     color = color + (noise - 0.5) * settings.noise_intensity;
     
-    // Apply brightness adjustment
-    color = color * settings.brightness;
+    // Apply brightness adjustment, flaring up as the picture collapses
+    color = color * settings.brightness * (1.0 + settings.collapse * 3.0);
     
     // Slight phosphor glow (bloom simulation) - brighten bright areas slightly
     let luminance = dot(color, vec3<f32>(0.299, 0.587, 0.114));
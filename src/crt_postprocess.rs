@@ -215,6 +215,12 @@ pub struct CrtSettings {
     pub noise_intensity: f32,
     /// Time for animated effects (scanline flicker, noise)
     pub time: f32,
+    /// Vertical flip (0.0 = upright, 1.0 = upside down). In-between values squash the picture
+    /// through its middle.
+    pub flip: f32,
+    /// Switch-off collapse (0.0 = normal, 0.5 = a bright horizontal line, 1.0 = gone), like an
+    /// old tube being turned off.
+    pub collapse: f32,
 }
 
 impl Default for CrtSettings {
@@ -228,6 +234,8 @@ impl Default for CrtSettings {
             brightness: 1.,
             noise_intensity: 0.003,
             time: 0.0,
+            flip: 0.0,
+            collapse: 0.0,
         }
     }
 }
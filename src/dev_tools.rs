@@ -1,11 +1,22 @@
 //! Development tools for the game. This plugin is only enabled in dev builds.
 
 use bevy::{
-    dev_tools::states::log_transitions, input::common_conditions::input_just_pressed, prelude::*,
+    camera_controller::free_camera::{FreeCamera, FreeCameraPlugin},
+    dev_tools::states::log_transitions,
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+    window::PrimaryWindow,
 };
 use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
 
-use crate::screens::Screen;
+use crate::{
+    IsometricCamera, Pause, PauseHeld,
+    game::{
+        GameAssets, GameStateMachine, HazardKind, LEVEL_PATH, LevelFile, LevelHazard, LevelMirror,
+        LevelWall, SpawnZone, toggle_curse,
+    },
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(EguiPlugin::default())
@@ -21,10 +32,505 @@ pub(super) fn plugin(app: &mut App) {
         Update,
         toggle_debug_ui.run_if(input_just_pressed(TOGGLE_KEY)),
     );
+
+    // Curse or un-curse the player's controls.
+    app.add_systems(
+        Update,
+        // The level editor flies down with the same key.
+        toggle_curse.run_if(
+            in_state(GameStateMachine::Level)
+                .and(input_just_pressed(CURSE_KEY))
+                .and(not(resource_exists::<LevelEditor>)),
+        ),
+    );
+
+    // Edit the running level's layout and export it to the level file.
+    app.add_plugins(FreeCameraPlugin);
+    app.add_systems(
+        Update,
+        toggle_level_editor.run_if(
+            in_state(GameStateMachine::Level)
+                .and(resource_exists::<GameAssets>)
+                .and(input_just_pressed(EDITOR_KEY)),
+        ),
+    );
+    app.add_systems(
+        Update,
+        (
+            add_editor_item,
+            edit_selected_item,
+            export_level.run_if(input_just_pressed(EXPORT_KEY)),
+            draw_editor_items,
+        )
+            .run_if(resource_exists::<LevelEditor>),
+    );
+    app.add_systems(OnExit(GameStateMachine::Level), close_level_editor);
 }
 
 const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+const CURSE_KEY: KeyCode = KeyCode::KeyQ;
+const EDITOR_KEY: KeyCode = KeyCode::F2;
+const EXPORT_KEY: KeyCode = KeyCode::F5;
 
 fn toggle_debug_ui(mut options: ResMut<UiDebugOptions>) {
     options.toggle();
 }
+
+/// How far one press of the rotate keys turns the selected item, in degrees.
+const EDITOR_ROTATION_STEP: f32 = 15.0;
+/// How much one press of the resize keys grows or shrinks the selected item, in metres.
+const EDITOR_RESIZE_STEP: f32 = 0.5;
+
+const WALL_SIZE: Vec3 = vec3(4.0, 2.0, 0.3);
+const MIRROR_SIZE: Vec3 = vec3(3.0, 4.0, 0.12);
+const SPAWN_ZONE_RADIUS: f32 = 3.0;
+const HAZARD_RADIUS: f32 = 4.0;
+const TORCH_SLOT_MARKER_SIZE: f32 = 0.5;
+
+/// The level editor is open. Holds what it needs to put things back when it closes.
+#[derive(Resource)]
+struct LevelEditor {
+    /// Where the camera was before it started flying around.
+    camera: Transform,
+    selected: Option<Entity>,
+}
+
+/// Something in the layout the editor can move. The entity's scale holds its size, and a torch
+/// slot holds its place in the order torches are bought.
+#[derive(Component, Clone, Debug)]
+enum EditorItem {
+    Wall { name: String },
+    Mirror,
+    TorchSlot(usize),
+    SpawnZone,
+    StreetLamp,
+    Hazard(HazardKind),
+}
+
+impl EditorItem {
+    fn color(&self) -> Color {
+        match self {
+            EditorItem::Wall { .. } => Color::srgba(0.9, 0.9, 0.9, 0.3),
+            EditorItem::Mirror => Color::srgba(0.0, 0.8, 1.0, 0.4),
+            EditorItem::TorchSlot(_) => Color::srgba(1.0, 0.5, 0.1, 0.6),
+            EditorItem::SpawnZone => Color::srgba(0.8, 0.0, 0.2, 0.3),
+            EditorItem::StreetLamp => Color::srgba(1.0, 0.9, 0.3, 0.6),
+            EditorItem::Hazard(HazardKind::Dark) => Color::srgba(0.3, 0.0, 0.5, 0.4),
+            EditorItem::Hazard(HazardKind::Fog) => Color::srgba(0.7, 0.8, 0.75, 0.3),
+        }
+    }
+
+    fn mesh(&self) -> Mesh {
+        match self {
+            EditorItem::SpawnZone | EditorItem::Hazard(_) => Cylinder::new(0.5, 1.0).into(),
+            _ => Cuboid::from_length(1.0).into(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct LevelEditorText;
+
+fn toggle_level_editor(
+    mut commands: Commands,
+    editor: Option<Res<LevelEditor>>,
+    camera: Single<(Entity, &Transform), With<IsometricCamera>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+) {
+    if editor.is_some() {
+        commands.run_system_cached(close_level_editor);
+        return;
+    }
+
+    let (camera, camera_transform) = *camera;
+    commands.insert_resource(LevelEditor {
+        camera: *camera_transform,
+        selected: None,
+    });
+    commands.entity(camera).insert(FreeCamera {
+        // Left click is for picking things up.
+        mouse_key_cursor_grab: MouseButton::Right,
+        ..default()
+    });
+    commands.insert_resource(PauseHeld);
+    next_pause.set(Pause(true));
+
+    let level = assets.level(&levels);
+    let items = level
+        .walls
+        .iter()
+        .map(|wall| {
+            let item = EditorItem::Wall {
+                name: wall.name.clone(),
+            };
+            (item, wall.transform.with_scale(wall.size))
+        })
+        .chain(
+            level
+                .mirrors
+                .iter()
+                .map(|mirror| (EditorItem::Mirror, mirror.transform.with_scale(mirror.size))),
+        )
+        .chain(level.torch_slots.iter().enumerate().map(|(index, slot)| {
+            (
+                EditorItem::TorchSlot(index),
+                Transform::from_translation(*slot).with_scale(Vec3::splat(TORCH_SLOT_MARKER_SIZE)),
+            )
+        }))
+        .chain(level.street_lamps.iter().map(|lamp| {
+            (
+                EditorItem::StreetLamp,
+                Transform::from_translation(*lamp).with_scale(Vec3::splat(TORCH_SLOT_MARKER_SIZE)),
+            )
+        }))
+        .chain(level.spawn_zones.iter().map(|zone| {
+            (
+                EditorItem::SpawnZone,
+                spawn_zone_transform(zone.center, zone.radius),
+            )
+        }))
+        .chain(level.hazards.iter().map(|hazard| {
+            (
+                EditorItem::Hazard(hazard.kind),
+                spawn_zone_transform(hazard.center, hazard.radius),
+            )
+        }));
+    for (item, transform) in items {
+        spawn_editor_item(&mut commands, &mut meshes, &mut materials, item, transform);
+    }
+
+    commands.spawn((
+        Name::new("Level Editor Text"),
+        LevelEditorText,
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(8),
+            right: px(8),
+            ..default()
+        },
+        GlobalZIndex(2),
+        Text::new(format!(
+            "LEVEL EDITOR\n\
+             Fly: WASD, Q/E, hold right mouse to look\n\
+             Add: 1 wall, 2 mirror, 3 torch slot, 4 spawn zone, 5 street lamp, 6 dark zone, 7 fog\n\
+             Click to select, drag to move\n\
+             R / Shift+R rotate, = / - resize, Delete remove\n\
+             {EXPORT_KEY:?} export to {LEVEL_PATH}, {EDITOR_KEY:?} close"
+        )),
+        TextFont::from_font_size(14.0),
+    ));
+}
+
+fn close_level_editor(
+    mut commands: Commands,
+    editor: Option<Res<LevelEditor>>,
+    mut camera: Single<(Entity, &mut Transform), With<IsometricCamera>>,
+    items: Query<Entity, Or<(With<EditorItem>, With<LevelEditorText>)>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+) {
+    let Some(editor) = editor else {
+        return;
+    };
+    let (camera, ref mut camera_transform) = *camera;
+    **camera_transform = editor.camera;
+    commands.entity(camera).remove::<FreeCamera>();
+    for item in &items {
+        commands.entity(item).despawn();
+    }
+    commands.remove_resource::<LevelEditor>();
+    commands.remove_resource::<PauseHeld>();
+    next_pause.set(Pause(false));
+}
+
+fn spawn_zone_transform(center: Vec3, radius: f32) -> Transform {
+    Transform::from_translation(center.with_y(0.05)).with_scale(vec3(
+        radius * 2.0,
+        0.1,
+        radius * 2.0,
+    ))
+}
+
+fn spawn_editor_item(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    item: EditorItem,
+    transform: Transform,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new("Editor Item"),
+            DespawnOnExit(GameStateMachine::Level),
+            DespawnOnExit(Screen::Gameplay),
+            Mesh3d(meshes.add(item.mesh())),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: item.color(),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })),
+            transform,
+            Pickable::default(),
+            item,
+        ))
+        .observe(select_item)
+        .observe(drag_item)
+        .id()
+}
+
+/// Where the cursor points on the ground.
+fn ground_point(camera: &Camera, camera_transform: &GlobalTransform, cursor: Vec2) -> Option<Vec3> {
+    let ray = camera.viewport_to_world(camera_transform, cursor).ok()?;
+    let distance = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))?;
+    Some(ray.get_point(distance))
+}
+
+fn select_item(click: On<Pointer<Click>>, mut editor: ResMut<LevelEditor>) {
+    editor.selected = Some(click.event_target());
+}
+
+fn drag_item(
+    drag: On<Pointer<Drag>>,
+    mut editor: ResMut<LevelEditor>,
+    camera: Single<(&Camera, &GlobalTransform), With<IsometricCamera>>,
+    mut items: Query<&mut Transform, With<EditorItem>>,
+) {
+    if drag.button != PointerButton::Primary {
+        return;
+    }
+    let (camera, camera_transform) = *camera;
+    let Some(point) = ground_point(camera, camera_transform, drag.pointer_location.position) else {
+        return;
+    };
+    if let Ok(mut transform) = items.get_mut(drag.event_target()) {
+        transform.translation.x = point.x;
+        transform.translation.z = point.z;
+        editor.selected = Some(drag.event_target());
+    }
+}
+
+fn add_editor_item(
+    mut commands: Commands,
+    mut editor: ResMut<LevelEditor>,
+    keys: Res<ButtonInput<KeyCode>>,
+    existing: Query<&EditorItem>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<IsometricCamera>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let item = if keys.just_pressed(KeyCode::Digit1) {
+        EditorItem::Wall {
+            name: "Wall".to_string(),
+        }
+    } else if keys.just_pressed(KeyCode::Digit2) {
+        EditorItem::Mirror
+    } else if keys.just_pressed(KeyCode::Digit3) {
+        // New slots go to the end of the buying order.
+        let next = existing
+            .iter()
+            .filter_map(|item| match item {
+                EditorItem::TorchSlot(index) => Some(index + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        EditorItem::TorchSlot(next)
+    } else if keys.just_pressed(KeyCode::Digit4) {
+        EditorItem::SpawnZone
+    } else if keys.just_pressed(KeyCode::Digit5) {
+        EditorItem::StreetLamp
+    } else if keys.just_pressed(KeyCode::Digit6) {
+        EditorItem::Hazard(HazardKind::Dark)
+    } else if keys.just_pressed(KeyCode::Digit7) {
+        EditorItem::Hazard(HazardKind::Fog)
+    } else {
+        return;
+    };
+    let (camera, camera_transform) = *camera;
+    let Some(point) = window
+        .cursor_position()
+        .and_then(|cursor| ground_point(camera, camera_transform, cursor))
+    else {
+        return;
+    };
+
+    let transform = match item {
+        EditorItem::Wall { .. } => {
+            Transform::from_translation(point.with_y(WALL_SIZE.y / 2.0)).with_scale(WALL_SIZE)
+        }
+        EditorItem::Mirror => {
+            Transform::from_translation(point.with_y(MIRROR_SIZE.y / 2.0)).with_scale(MIRROR_SIZE)
+        }
+        EditorItem::TorchSlot(_) | EditorItem::StreetLamp => {
+            Transform::from_translation(point.with_y(0.0))
+                .with_scale(Vec3::splat(TORCH_SLOT_MARKER_SIZE))
+        }
+        EditorItem::SpawnZone => spawn_zone_transform(point, SPAWN_ZONE_RADIUS),
+        EditorItem::Hazard(_) => spawn_zone_transform(point, HAZARD_RADIUS),
+    };
+    let entity = spawn_editor_item(&mut commands, &mut meshes, &mut materials, item, transform);
+    editor.selected = Some(entity);
+}
+
+fn edit_selected_item(
+    mut commands: Commands,
+    mut editor: ResMut<LevelEditor>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut items: Query<(&EditorItem, &mut Transform)>,
+) {
+    let Some(selected) = editor.selected else {
+        return;
+    };
+    let Ok((item, mut transform)) = items.get_mut(selected) else {
+        editor.selected = None;
+        return;
+    };
+
+    if keys.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        commands.entity(selected).despawn();
+        editor.selected = None;
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::KeyR) {
+        let step = if keys.pressed(KeyCode::ShiftLeft) {
+            -EDITOR_ROTATION_STEP
+        } else {
+            EDITOR_ROTATION_STEP
+        };
+        transform.rotate_y(step.to_radians());
+    }
+
+    let resize = if keys.just_pressed(KeyCode::Equal) {
+        EDITOR_RESIZE_STEP
+    } else if keys.just_pressed(KeyCode::Minus) {
+        -EDITOR_RESIZE_STEP
+    } else {
+        return;
+    };
+    let scale = &mut transform.scale;
+    match item {
+        // Walls and mirrors get longer or shorter along their longer side.
+        EditorItem::Wall { .. } | EditorItem::Mirror => {
+            let length = if scale.x >= scale.z {
+                &mut scale.x
+            } else {
+                &mut scale.z
+            };
+            *length = (*length + resize).max(EDITOR_RESIZE_STEP);
+        }
+        // Spawn zones and hazards grow or shrink their radius.
+        EditorItem::SpawnZone | EditorItem::Hazard(_) => {
+            let diameter = (scale.x + resize * 2.0).max(EDITOR_RESIZE_STEP * 2.0);
+            scale.x = diameter;
+            scale.z = diameter;
+        }
+        EditorItem::TorchSlot(_) | EditorItem::StreetLamp => {}
+    }
+}
+
+/// Outlines the selected item so it's clear what the keys will change.
+fn draw_editor_items(
+    editor: Res<LevelEditor>,
+    items: Query<(&EditorItem, &Transform)>,
+    mut gizmos: Gizmos,
+) {
+    let Some((item, transform)) = editor
+        .selected
+        .and_then(|selected| items.get(selected).ok())
+    else {
+        return;
+    };
+    let color = Color::srgb(1.0, 1.0, 0.0);
+    match item {
+        EditorItem::SpawnZone | EditorItem::Hazard(_) => {
+            gizmos.circle(
+                Isometry3d::new(
+                    transform.translation,
+                    Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+                ),
+                transform.scale.x / 2.0,
+                color,
+            );
+        }
+        _ => gizmos.cube(*transform, color),
+    }
+}
+
+/// Writes the edited layout over the level file. Everything the editor doesn't touch, such as
+/// props, is kept as it was, but comments other than the file's header are lost. With the file
+/// watcher on, the arena rebuilds from the new file.
+fn export_level(
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    items: Query<(&EditorItem, &Transform)>,
+) {
+    let current = assets.level(&levels);
+    let mut level = LevelFile {
+        player_start: current.player_start,
+        generate_pieces: current.generate_pieces,
+        walls: Vec::new(),
+        props: current.props.clone(),
+        mirrors: Vec::new(),
+        torch_slots: Vec::new(),
+        street_lamps: Vec::new(),
+        spawn_zones: Vec::new(),
+        hazards: Vec::new(),
+    };
+    let mut torch_slots = Vec::new();
+    for (item, transform) in &items {
+        let placed =
+            Transform::from_translation(transform.translation).with_rotation(transform.rotation);
+        match item {
+            EditorItem::Wall { name } => level.walls.push(LevelWall {
+                name: name.clone(),
+                transform: placed,
+                size: transform.scale,
+            }),
+            EditorItem::Mirror => level.mirrors.push(LevelMirror {
+                transform: placed,
+                size: transform.scale,
+            }),
+            EditorItem::TorchSlot(index) => torch_slots.push((*index, transform.translation)),
+            EditorItem::StreetLamp => level.street_lamps.push(transform.translation),
+            EditorItem::SpawnZone => level.spawn_zones.push(SpawnZone {
+                center: transform.translation.with_y(0.0),
+                radius: transform.scale.x / 2.0,
+            }),
+            EditorItem::Hazard(kind) => level.hazards.push(LevelHazard {
+                kind: *kind,
+                center: transform.translation.with_y(0.0),
+                radius: transform.scale.x / 2.0,
+            }),
+        }
+    }
+
+    // Queries don't keep the order things were spawned in.
+    torch_slots.sort_by_key(|(index, _)| *index);
+    level.torch_slots = torch_slots.into_iter().map(|(_, slot)| slot).collect();
+
+    match level.to_ron() {
+        Ok(contents) => save_level(&contents),
+        Err(err) => warn!("Couldn't write out the level: {err}"),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_level(contents: &str) {
+    let path = format!("assets/{LEVEL_PATH}");
+    match std::fs::write(&path, contents) {
+        Ok(()) => info!("Exported the level to {path}"),
+        Err(err) => warn!("Couldn't save {path}: {err}"),
+    }
+}
+
+/// There's no file system on the web, so the level goes to the console to copy by hand.
+#[cfg(target_family = "wasm")]
+fn save_level(contents: &str) {
+    info!("Exported level for {LEVEL_PATH}:\n{contents}");
+}
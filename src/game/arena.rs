@@ -0,0 +1,231 @@
+//! Seeded arena layouts. Each night gets its own mix of pillars, ruined walls, corridors and
+//! open fields inside the outer walls, and the same seed always builds the same arena.
+
+use bevy::prelude::*;
+use bevy_rand::prelude::WyRand;
+use rand::{Rng, SeedableRng};
+
+use crate::game::{GameState, biome::Biome, level::ARENA_SIZE, level_file::LevelFile};
+
+/// The arena is laid out as a grid of square cells, each holding one kind of feature.
+const CELL_SIZE: f32 = 10.0;
+const CELLS: i32 = (ARENA_SIZE / CELL_SIZE) as i32;
+
+/// Nothing is built this close to the player's spawn point.
+const SPAWN_CLEARANCE: f32 = 6.0;
+/// Torches can't be placed this close to a piece, and street lamps this close to one are left
+/// out for the night.
+pub const TORCH_CLEARANCE: f32 = 2.0;
+/// Pieces keep this far from the outer walls so the edge of the arena stays walkable.
+const EDGE_MARGIN: f32 = 2.0;
+
+const PIECE_HEIGHT: f32 = 2.0;
+const PILLAR_HEIGHT: f32 = 3.0;
+const RUIN_THICKNESS: f32 = 0.6;
+/// Width of the walkway between a corridor's walls.
+const CORRIDOR_WIDTH: f32 = 3.0;
+
+/// What a cell of the arena holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Feature {
+    OpenField,
+    Pillars,
+    Ruin,
+    Corridor,
+}
+
+impl Feature {
+    fn roll(rng: &mut WyRand) -> Self {
+        match rng.random_range(0..20) {
+            0..7 => Feature::OpenField,
+            7..12 => Feature::Pillars,
+            12..17 => Feature::Ruin,
+            _ => Feature::Corridor,
+        }
+    }
+}
+
+/// How a piece is laid out. The biome decides what it looks like, and every kind collides as
+/// its box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceKind {
+    Pillar,
+    Ruin,
+    Corridor,
+}
+
+impl PieceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceKind::Pillar => "Pillar",
+            PieceKind::Ruin => "Ruined Wall",
+            PieceKind::Corridor => "Corridor Wall",
+        }
+    }
+}
+
+/// A solid, axis-aligned box standing on the ground.
+#[derive(Clone, Copy, Debug)]
+pub struct ArenaPiece {
+    pub kind: PieceKind,
+    /// The center of the box.
+    pub position: Vec3,
+    pub size: Vec3,
+}
+
+impl ArenaPiece {
+    fn new(kind: PieceKind, center: Vec2, footprint: Vec2, height: f32) -> Self {
+        Self {
+            kind,
+            position: vec3(center.x, height / 2.0, center.y),
+            size: vec3(footprint.x, height, footprint.y),
+        }
+    }
+
+    /// Whether a circle on the ground at `point` overlaps this piece.
+    pub fn blocks(&self, point: Vec3, radius: f32) -> bool {
+        let half = self.size.xz() / 2.0;
+        let offset = (point.xz() - self.position.xz()).abs();
+        let outside = (offset - half).max(Vec2::ZERO);
+        outside.length() < radius
+    }
+}
+
+/// The inside of one night's arena.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ArenaLayout {
+    pub seed: u64,
+    pub biome: Biome,
+    pub pieces: Vec<ArenaPiece>,
+}
+
+impl ArenaLayout {
+    /// Lays out an arena in `biome` from `seed`, leaving room around the player's `start`. The
+    /// same inputs always give the same arena.
+    pub fn generate(seed: u64, biome: Biome, start: Vec3) -> Self {
+        let mut rng = WyRand::seed_from_u64(seed);
+        let mut pieces = Vec::new();
+        let half = CELLS as f32 / 2.0;
+
+        for row in 0..CELLS {
+            for column in 0..CELLS {
+                let feature = Feature::roll(&mut rng);
+                let corner = vec2(column as f32 - half, row as f32 - half) * CELL_SIZE;
+                let cell = Rect::from_corners(corner, corner + Vec2::splat(CELL_SIZE));
+                match feature {
+                    Feature::OpenField => {}
+                    Feature::Pillars => pillars(&mut rng, cell, &mut pieces),
+                    Feature::Ruin => ruin(&mut rng, cell, &mut pieces),
+                    Feature::Corridor => corridor(&mut rng, cell, &mut pieces),
+                }
+            }
+        }
+
+        let limit = ARENA_SIZE / 2.0 - EDGE_MARGIN;
+        pieces.retain(|piece| {
+            let half_size = piece.size.xz() / 2.0;
+            let inside = (piece.position.xz().abs() + half_size).max_element() <= limit;
+            inside && !piece.blocks(start, SPAWN_CLEARANCE)
+        });
+
+        Self {
+            seed,
+            biome,
+            pieces,
+        }
+    }
+
+    /// Whether a circle on the ground at `point` is clear of every piece.
+    pub fn is_clear(&self, point: Vec3, radius: f32) -> bool {
+        !self.pieces.iter().any(|piece| piece.blocks(point, radius))
+    }
+}
+
+/// A few pillars scattered about the cell.
+fn pillars(rng: &mut WyRand, cell: Rect, pieces: &mut Vec<ArenaPiece>) {
+    let inner = cell.inflate(-2.0);
+    for _ in 0..rng.random_range(2..=4) {
+        let center = vec2(
+            rng.random_range(inner.min.x..inner.max.x),
+            rng.random_range(inner.min.y..inner.max.y),
+        );
+        let width = rng.random_range(0.8..1.6);
+        pieces.push(ArenaPiece::new(
+            PieceKind::Pillar,
+            center,
+            Vec2::splat(width),
+            PILLAR_HEIGHT,
+        ));
+    }
+}
+
+/// What's left of a wall: a run across the cell broken by a gap to squeeze through.
+fn ruin(rng: &mut WyRand, cell: Rect, pieces: &mut Vec<ArenaPiece>) {
+    let horizontal = rng.random_bool(0.5);
+    let length = rng.random_range(5.0..CELL_SIZE - 1.0);
+    let gap = rng.random_range(1.5..2.5);
+    let gap_at = rng.random_range(1.0..length - gap - 1.0);
+    let across = rng.random_range(2.0..CELL_SIZE - 2.0);
+    let start = (CELL_SIZE - length) / 2.0;
+
+    for (from, to) in [(0.0, gap_at), (gap_at + gap, length)] {
+        let run = to - from;
+        let along = start + from + run / 2.0;
+        let height = PIECE_HEIGHT * rng.random_range(0.5..1.0);
+        let (offset, footprint) = if horizontal {
+            (vec2(along, across), vec2(run, RUIN_THICKNESS))
+        } else {
+            (vec2(across, along), vec2(RUIN_THICKNESS, run))
+        };
+        pieces.push(ArenaPiece::new(
+            PieceKind::Ruin,
+            cell.min + offset,
+            footprint,
+            height,
+        ));
+    }
+}
+
+/// Two parallel walls running the length of the cell, open at both ends.
+fn corridor(rng: &mut WyRand, cell: Rect, pieces: &mut Vec<ArenaPiece>) {
+    let horizontal = rng.random_bool(0.5);
+    let length = CELL_SIZE - 1.0;
+    let middle = CELL_SIZE / 2.0;
+
+    for side in [-1.0, 1.0] {
+        let across = middle + side * (CORRIDOR_WIDTH + RUIN_THICKNESS) / 2.0;
+        let (offset, footprint) = if horizontal {
+            (vec2(middle, across), vec2(length, RUIN_THICKNESS))
+        } else {
+            (vec2(across, middle), vec2(RUIN_THICKNESS, length))
+        };
+        pieces.push(ArenaPiece::new(
+            PieceKind::Corridor,
+            cell.min + offset,
+            footprint,
+            PIECE_HEIGHT,
+        ));
+    }
+}
+
+impl GameState {
+    /// The seed for the given night's arena. Every night of a run gets a different arena, but
+    /// replaying a run's seed rebuilds the same ones.
+    pub fn arena_seed(&self, night_number: usize) -> u64 {
+        self.arena_seed ^ (night_number as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    /// The given night's arena on top of `level`. Hand-made levels can opt out of seeded pieces.
+    pub fn arena_layout(&self, night_number: usize, level: &LevelFile) -> ArenaLayout {
+        let seed = self.arena_seed(night_number);
+        let biome = self.biome(night_number);
+        if !level.generate_pieces {
+            return ArenaLayout {
+                seed,
+                biome,
+                pieces: Vec::new(),
+            };
+        }
+        ArenaLayout::generate(seed, biome, level.player_start)
+    }
+}
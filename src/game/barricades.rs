@@ -0,0 +1,247 @@
+//! Barricades the player builds between nights. They stop enemies where walls don't, so an
+//! enemy with a barricade between it and the player stops to break it down. Barricades that
+//! get broken are gone until the player builds new ones.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_seedling::sample::SamplePlayer;
+
+use crate::{
+    PausableSystems,
+    game::{
+        Barricade, GameAssets, GameState, GameStateMachine,
+        arena::ArenaLayout,
+        level::{BARRICADE_GROUP, Enemy, Health, Player},
+        level_file::LevelFile,
+    },
+    screens::Screen,
+};
+
+/// Gold a barricade costs to build. Taking one down before a night refunds it.
+pub(super) const BARRICADE_COST: usize = 30;
+pub(super) const MAX_BARRICADES: usize = 6;
+/// Width, height and thickness.
+pub(super) const BARRICADE_SIZE: Vec3 = vec3(3.0, 1.2, 0.4);
+const BARRICADE_HEALTH: f32 = 100.0;
+/// Damage per second each enemy deals to a barricade it's up against.
+const BREAK_DAMAGE_PER_SECOND: f32 = 8.0;
+/// How close to the barricade an enemy has to be to hit it.
+const BREAK_DISTANCE: f32 = 1.0;
+/// Barricades keep this far from the arena's pieces, checked at points this far apart along
+/// their length.
+const PIECE_CLEARANCE: f32 = 0.5;
+/// Enemies farther from a barricade than this just keep coming.
+const BESIEGE_RANGE: f32 = 10.0;
+
+const SOUND_COLOR: Color = Color::srgb(0.45, 0.3, 0.15);
+const BROKEN_COLOR: Color = Color::srgb(0.12, 0.08, 0.05);
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameStateMachine::Level), spawn_barricades);
+    app.add_systems(
+        Update,
+        (besiege_barricades, break_barricades, show_barricade_damage)
+            .chain()
+            .run_if(resource_exists::<GameAssets>)
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+}
+
+impl Barricade {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position.with_y(BARRICADE_SIZE.y / 2.0))
+            .with_rotation(Quat::from_rotation_y(self.yaw))
+    }
+
+    /// The ends of the line running along the middle of the planks, on the ground.
+    fn ends(&self) -> (Vec2, Vec2) {
+        let half = (Quat::from_rotation_y(self.yaw) * Vec3::X * BARRICADE_SIZE.x / 2.0).xz();
+        let center = self.position.xz();
+        (center - half, center + half)
+    }
+
+    /// How far a point on the ground is from the barricade's footprint. The footprint's ends are
+    /// rounded off, which is close enough for keeping things apart.
+    pub fn distance_to(&self, point: Vec3) -> f32 {
+        let (start, end) = self.ends();
+        (segment_distance(start, end, point.xz()) - BARRICADE_SIZE.z / 2.0).max(0.0)
+    }
+
+    /// How far apart the footprints of two barricades are. Zero when they overlap.
+    pub fn distance_to_barricade(&self, other: &Barricade) -> f32 {
+        let (a, b) = self.ends();
+        let (c, d) = other.ends();
+        // Side of the line through `from` and `to` that `point` is on.
+        let side = |from: Vec2, to: Vec2, point: Vec2| (to - from).perp_dot(point - from);
+        let crossing = side(a, b, c) * side(a, b, d) < 0.0 && side(c, d, a) * side(c, d, b) < 0.0;
+        if crossing {
+            return 0.0;
+        }
+        let between_lines = segment_distance(a, b, c)
+            .min(segment_distance(a, b, d))
+            .min(segment_distance(c, d, a))
+            .min(segment_distance(c, d, b));
+        (between_lines - BARRICADE_SIZE.z).max(0.0)
+    }
+
+    /// Whether the barricade stays clear of the pieces of `layout`. Pieces differ from night to
+    /// night, so one that fit when it was built can be in the way later.
+    pub fn fits(&self, layout: &ArenaLayout) -> bool {
+        self.points_along(PIECE_CLEARANCE)
+            .all(|point| layout.is_clear(point, PIECE_CLEARANCE))
+    }
+
+    /// Points spaced `spacing` apart along the barricade, from one end to the other.
+    fn points_along(&self, spacing: f32) -> impl Iterator<Item = Vec3> {
+        let (start, end) = self.ends();
+        let steps = (BARRICADE_SIZE.x / spacing).ceil().max(1.0) as usize;
+        (0..=steps).map(move |step| {
+            let point = start.lerp(end, step as f32 / steps as f32);
+            vec3(point.x, 0.0, point.y)
+        })
+    }
+}
+
+/// Distance from `point` to the closest point on the segment between `start` and `end`.
+fn segment_distance(start: Vec2, end: Vec2, point: Vec2) -> f32 {
+    let along = end - start;
+    let t = ((point - start).dot(along) / along.length_squared()).clamp(0.0, 1.0);
+    point.distance(start + along * t)
+}
+
+/// The planks of a barricade, without anything that collides.
+pub(super) fn barricade_model(
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    barricade: &Barricade,
+) -> impl Bundle {
+    (
+        Mesh3d(meshes.add(Cuboid::from_size(BARRICADE_SIZE))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: SOUND_COLOR,
+            perceptual_roughness: 0.9,
+            ..default()
+        })),
+        barricade.transform(),
+    )
+}
+
+/// A barricade standing in the arena during a night.
+#[derive(Component)]
+struct BarricadeWall(Barricade);
+
+/// An enemy breaking down the barricade between it and the player.
+#[derive(Component)]
+pub(super) struct Besieging {
+    pub target: Entity,
+    /// Where the enemy meets the barricade.
+    pub position: Vec3,
+}
+
+fn spawn_barricades(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    game_state: Res<GameState>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+) {
+    let layout = game_state.arena_layout(game_state.night_number, assets.level(&levels));
+    let half_extents = BARRICADE_SIZE / 2.0;
+    // The placement screen takes down barricades that don't fit, but the night can start
+    // without it.
+    for barricade in game_state
+        .barricades
+        .iter()
+        .filter(|barricade| barricade.fits(&layout))
+    {
+        commands.spawn((
+            Name::new("Barricade"),
+            DespawnOnExit(GameStateMachine::Level),
+            DespawnOnExit(Screen::Gameplay),
+            BarricadeWall(*barricade),
+            Health(BARRICADE_HEALTH),
+            barricade_model(&mut meshes, &mut materials, barricade),
+            RigidBody::Fixed,
+            Collider::cuboid(half_extents.x, half_extents.y, half_extents.z),
+            CollisionGroups::new(BARRICADE_GROUP, Group::ALL),
+        ));
+    }
+}
+
+/// Sends every enemy with a barricade between it and the player at that barricade.
+fn besiege_barricades(
+    mut commands: Commands,
+    rapier_context: ReadRapierContext,
+    enemies: Query<(Entity, &Transform), With<Enemy>>,
+    player: Single<&Transform, (With<Player>, Without<Enemy>)>,
+) {
+    let rapier_context = rapier_context.single().unwrap();
+    let filter = QueryFilter::default().groups(CollisionGroups::new(Group::ALL, BARRICADE_GROUP));
+    let height = BARRICADE_SIZE.y / 2.0;
+    let target = player.translation.with_y(height);
+
+    for (entity, transform) in &enemies {
+        let origin = transform.translation.with_y(height);
+        let offset = target - origin;
+        let direction = offset.normalize_or_zero();
+        let range = offset.length().min(BESIEGE_RANGE);
+        match rapier_context.cast_ray(origin, direction, range, true, filter) {
+            Some((barricade, toi)) => {
+                commands.entity(entity).try_insert(Besieging {
+                    target: barricade,
+                    position: origin + direction * toi,
+                });
+            }
+            None => {
+                commands.entity(entity).try_remove::<Besieging>();
+            }
+        }
+    }
+}
+
+fn break_barricades(
+    mut commands: Commands,
+    enemies: Query<(&Transform, &Besieging)>,
+    mut barricades: Query<(Entity, &BarricadeWall, &mut Health)>,
+    mut game_state: ResMut<GameState>,
+    assets: Res<GameAssets>,
+    time: Res<Time>,
+) {
+    for (transform, besieging) in &enemies {
+        if transform.translation.xz().distance(besieging.position.xz()) > BREAK_DISTANCE {
+            continue;
+        }
+        if let Ok((_, _, mut health)) = barricades.get_mut(besieging.target) {
+            health.0 -= BREAK_DAMAGE_PER_SECOND * time.delta_secs();
+        }
+    }
+
+    for (entity, wall, health) in &barricades {
+        if health.0 > 0.0 {
+            continue;
+        }
+        game_state
+            .barricades
+            .retain(|barricade| *barricade != wall.0);
+        commands.spawn(SamplePlayer::new(assets.pop_sound.clone()));
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Barricades char darker as they take a beating.
+fn show_barricade_damage(
+    barricades: Query<
+        (&Health, &MeshMaterial3d<StandardMaterial>),
+        (With<BarricadeWall>, Changed<Health>),
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (health, material) in &barricades {
+        if let Some(material) = materials.get_mut(&material.0) {
+            let t = (health.0 / BARRICADE_HEALTH).clamp(0.0, 1.0);
+            material.base_color = BROKEN_COLOR.mix(&SOUND_COLOR, t);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Biomes a night can be played in. Each has its own floor, walls, piece shapes, ambient light
+//! and mix of enemies. The first night is always in the cellar, and the run's seed picks the rest.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::game::{GameAssets, GameState, LIGHT_COLOR, arena::PieceKind, level::EnemyArchetype};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum Biome {
+    #[default]
+    Cellar,
+    Graveyard,
+    Forest,
+}
+
+const BIOMES: [Biome; 3] = [Biome::Cellar, Biome::Graveyard, Biome::Forest];
+
+/// Distance between the posts of a graveyard fence.
+const FENCE_POST_SPACING: f32 = 1.0;
+const FENCE_POST_WIDTH: f32 = 0.12;
+const FENCE_RAIL_WIDTH: f32 = 0.08;
+/// Rough length of one bush in a forest hedge.
+const BUSH_LENGTH: f32 = 1.5;
+
+impl Biome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Biome::Cellar => "The Cellar",
+            Biome::Graveyard => "The Graveyard",
+            Biome::Forest => "The Forest",
+        }
+    }
+
+    /// A line for the night intro hinting at what lives there.
+    pub fn description(self) -> &'static str {
+        match self {
+            Biome::Cellar => "Damp boards and old brick. Crawlers nest in the dark corners.",
+            Biome::Graveyard => "Cold stone and colder light. The wraiths are restless tonight.",
+            Biome::Forest => "Tangled trunks and deep shadow. Stalkers hunt between the trees.",
+        }
+    }
+
+    pub fn floor_texture(self, assets: &GameAssets) -> Handle<Image> {
+        match self {
+            Biome::Cellar => assets.grass_texture.clone(),
+            Biome::Graveyard => assets.soil_texture.clone(),
+            Biome::Forest => assets.moss_texture.clone(),
+        }
+    }
+
+    pub fn wall_color(self) -> Color {
+        match self {
+            Biome::Cellar => Color::srgb(0.1, 0.1, 0.1),
+            Biome::Graveyard => Color::srgb(0.3, 0.3, 0.32),
+            Biome::Forest => Color::srgb(0.16, 0.11, 0.07),
+        }
+    }
+
+    /// The color of the seeded pieces: pillars are tombstones in the graveyard and trunks in
+    /// the forest.
+    pub fn piece_color(self, kind: PieceKind) -> Color {
+        match (self, kind) {
+            (Biome::Cellar, PieceKind::Pillar) => Color::srgb(0.25, 0.24, 0.22),
+            (Biome::Cellar, PieceKind::Ruin) => Color::srgb(0.2, 0.18, 0.16),
+            (Biome::Cellar, PieceKind::Corridor) => Color::srgb(0.14, 0.14, 0.15),
+            (Biome::Graveyard, PieceKind::Pillar) => Color::srgb(0.45, 0.45, 0.47),
+            (Biome::Graveyard, PieceKind::Ruin) => Color::srgb(0.35, 0.35, 0.37),
+            (Biome::Graveyard, PieceKind::Corridor) => Color::srgb(0.25, 0.26, 0.27),
+            (Biome::Forest, PieceKind::Pillar) => Color::srgb(0.22, 0.15, 0.09),
+            (Biome::Forest, PieceKind::Ruin) => Color::srgb(0.18, 0.24, 0.14),
+            (Biome::Forest, PieceKind::Corridor) => Color::srgb(0.08, 0.18, 0.08),
+        }
+    }
+
+    /// The shapes a piece of the given `size` is built from here, around the middle of its box.
+    /// They stay inside the box, so the piece's collider still fits them.
+    pub fn piece_shapes(self, kind: PieceKind, size: Vec3) -> Vec<(Mesh, Transform)> {
+        match (self, kind) {
+            (Biome::Cellar, _) | (Biome::Graveyard, PieceKind::Corridor) => {
+                vec![(Cuboid::from_size(size).into(), Transform::IDENTITY)]
+            }
+            (Biome::Graveyard, PieceKind::Pillar) => headstone(size),
+            (Biome::Graveyard, PieceKind::Ruin) => fence(size),
+            (Biome::Forest, PieceKind::Pillar) => trunk(size),
+            (Biome::Forest, PieceKind::Ruin) => log_pile(size),
+            (Biome::Forest, PieceKind::Corridor) => hedge(size),
+        }
+    }
+
+    /// The tint of the ambient light before dawn washes it out.
+    pub fn ambient_color(self) -> Color {
+        match self {
+            Biome::Cellar => LIGHT_COLOR,
+            Biome::Graveyard => Color::srgb(0.6, 0.7, 1.0),
+            Biome::Forest => Color::srgb(0.6, 1.0, 0.5),
+        }
+    }
+
+    /// How often each archetype turns up, relative to the others.
+    fn enemy_weights(self) -> [(EnemyArchetype, u32); 5] {
+        use EnemyArchetype::*;
+        match self {
+            Biome::Cellar => [
+                (Shambler, 2),
+                (Stalker, 1),
+                (Wraith, 1),
+                (Brute, 1),
+                (Crawler, 3),
+            ],
+            Biome::Graveyard => [
+                (Shambler, 3),
+                (Stalker, 1),
+                (Wraith, 3),
+                (Brute, 1),
+                (Crawler, 1),
+            ],
+            Biome::Forest => [
+                (Shambler, 1),
+                (Stalker, 3),
+                (Wraith, 1),
+                (Brute, 2),
+                (Crawler, 1),
+            ],
+        }
+    }
+
+    /// The archetype that turns up here the most.
+    pub fn favored_enemy(self) -> EnemyArchetype {
+        self.enemy_weights()
+            .into_iter()
+            .max_by_key(|(_, weight)| *weight)
+            .map(|(archetype, _)| archetype)
+            .expect("every biome has enemies")
+    }
+
+    /// Rolls the archetype of a regular enemy spawning here.
+    pub fn random_enemy(self, rng: &mut impl Rng) -> EnemyArchetype {
+        let weights = self.enemy_weights();
+        let total: u32 = weights.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.random_range(0..total);
+        for (archetype, weight) in weights {
+            if roll < weight {
+                return archetype;
+            }
+            roll -= weight;
+        }
+        unreachable!("the roll is below the total weight")
+    }
+}
+
+/// The length and thickness of a wall-like piece, and the rotation that turns the X axis along
+/// its length.
+fn run(size: Vec3) -> (f32, f32, Quat) {
+    if size.x >= size.z {
+        (size.x, size.z, Quat::IDENTITY)
+    } else {
+        (size.z, size.x, Quat::from_rotation_y(FRAC_PI_2))
+    }
+}
+
+/// A slab with a rounded top.
+fn headstone(size: Vec3) -> Vec<(Mesh, Transform)> {
+    let radius = size.x / 2.0;
+    let body = size.y - radius;
+    vec![
+        (
+            Cuboid::new(size.x, body, size.z).into(),
+            Transform::from_xyz(0.0, (body - size.y) / 2.0, 0.0),
+        ),
+        (
+            Cylinder::new(radius, size.z).into(),
+            Transform::from_xyz(0.0, size.y / 2.0 - radius, 0.0)
+                .with_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+        ),
+    ]
+}
+
+/// Iron posts joined by two rails.
+fn fence(size: Vec3) -> Vec<(Mesh, Transform)> {
+    let (length, _, rotation) = run(size);
+    let span = length - FENCE_POST_WIDTH;
+    let gaps = (span / FENCE_POST_SPACING).ceil().max(1.0) as usize;
+    let mut shapes: Vec<(Mesh, Transform)> = (0..=gaps)
+        .map(|post| {
+            let along = span * (post as f32 / gaps as f32 - 0.5);
+            (
+                Cuboid::new(FENCE_POST_WIDTH, size.y, FENCE_POST_WIDTH).into(),
+                Transform::from_translation(rotation * Vec3::X * along),
+            )
+        })
+        .collect();
+    for height in [0.3, 0.85] {
+        shapes.push((
+            Cuboid::new(span, FENCE_RAIL_WIDTH, FENCE_RAIL_WIDTH).into(),
+            Transform::from_xyz(0.0, size.y * (height - 0.5), 0.0).with_rotation(rotation),
+        ));
+    }
+    shapes
+}
+
+fn trunk(size: Vec3) -> Vec<(Mesh, Transform)> {
+    let radius = size.x.min(size.z) / 2.0;
+    vec![(Cylinder::new(radius, size.y).into(), Transform::IDENTITY)]
+}
+
+/// Logs stacked on their sides.
+fn log_pile(size: Vec3) -> Vec<(Mesh, Transform)> {
+    let (length, thickness, rotation) = run(size);
+    let radius = thickness / 2.0;
+    let logs = (size.y / thickness).floor().max(1.0) as usize;
+    // Cylinders stand along Y, so turn them onto X before laying them along the run.
+    let lying = rotation * Quat::from_rotation_z(FRAC_PI_2);
+    (0..logs)
+        .map(|log| {
+            let height = -size.y / 2.0 + radius + thickness * log as f32;
+            (
+                Cylinder::new(radius, length).into(),
+                Transform::from_xyz(0.0, height, 0.0).with_rotation(lying),
+            )
+        })
+        .collect()
+}
+
+/// A row of squashed bushes.
+fn hedge(size: Vec3) -> Vec<(Mesh, Transform)> {
+    let (length, thickness, rotation) = run(size);
+    let bushes = (length / BUSH_LENGTH).round().max(1.0) as usize;
+    let bush = length / bushes as f32;
+    (0..bushes)
+        .map(|index| {
+            let along = -length / 2.0 + bush * (index as f32 + 0.5);
+            (
+                Sphere::new(0.5).into(),
+                Transform::from_translation(rotation * Vec3::X * along)
+                    .with_rotation(rotation)
+                    .with_scale(vec3(bush, size.y, thickness)),
+            )
+        })
+        .collect()
+}
+
+impl GameState {
+    /// The biome the given night is played in. Like the arena, it's fixed by the run's seed.
+    pub fn biome(&self, night_number: usize) -> Biome {
+        if night_number <= 1 {
+            return Biome::Cellar;
+        }
+        BIOMES[(self.arena_seed(night_number) >> 32) as usize % BIOMES.len()]
+    }
+}
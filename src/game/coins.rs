@@ -0,0 +1,119 @@
+//! Gold coins dropped by slain enemies. The player has to go and pick them up, coins slide
+//! over once the player is close, and whatever is still lying around at sunrise is lost.
+
+use bevy::prelude::*;
+use bevy_rand::{global::GlobalRng, prelude::WyRand};
+use bevy_seedling::sample::SamplePlayer;
+use rand::Rng;
+
+use crate::{
+    PausableSystems,
+    game::{
+        GameAssets, GameState, GameStateMachine,
+        level::{EnemyKilled, Player},
+        pickups::billboard,
+    },
+    screens::Screen,
+};
+
+/// Gold a single coin is worth.
+const COIN_VALUE: usize = 1;
+/// Coins within this distance of the player slide over to them.
+const COIN_MAGNET_RADIUS: f32 = 3.5;
+const COIN_MAGNET_SPEED: f32 = 8.0;
+/// How close the player has to get to pick a coin up.
+const COIN_PICKUP_RADIUS: f32 = 0.6;
+/// How far from the enemy a dropped coin can land.
+const COIN_SCATTER: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(drop_coin);
+    app.add_systems(
+        Update,
+        (attract_coins, collect_coins, vanish_at_sunrise)
+            .chain()
+            .run_if(resource_exists::<GameAssets>)
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+}
+
+#[derive(Component)]
+struct Coin(usize);
+
+fn drop_coin(
+    killed: On<EnemyKilled>,
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    game_state: Res<GameState>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    // Nobody's left to pick it up.
+    if game_state.is_sunrise() {
+        return;
+    }
+    let scatter = vec3(
+        rng.random_range(-COIN_SCATTER..COIN_SCATTER),
+        0.0,
+        rng.random_range(-COIN_SCATTER..COIN_SCATTER),
+    );
+    commands.spawn((
+        Name::new("Coin"),
+        Coin(COIN_VALUE),
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        Transform::from_translation((killed.position + scatter).with_y(0.5)),
+        Visibility::default(),
+        children![(
+            Transform::from_scale(Vec3::splat(0.5)),
+            billboard(assets.coin_image.clone()),
+        )],
+    ));
+}
+
+fn attract_coins(
+    player: Single<&Transform, (With<Player>, Without<Coin>)>,
+    mut coins: Query<&mut Transform, With<Coin>>,
+    time: Res<Time>,
+) {
+    let target = player.translation.xz();
+    for mut transform in &mut coins {
+        let offset = target - transform.translation.xz();
+        let distance = offset.length();
+        if distance > COIN_MAGNET_RADIUS {
+            continue;
+        }
+        let step = (COIN_MAGNET_SPEED * time.delta_secs()).min(distance);
+        let movement = offset.normalize_or_zero() * step;
+        transform.translation += vec3(movement.x, 0.0, movement.y);
+    }
+}
+
+fn collect_coins(
+    mut commands: Commands,
+    player: Single<&Transform, (With<Player>, Without<Coin>)>,
+    coins: Query<(Entity, &Coin, &Transform)>,
+    assets: Res<GameAssets>,
+    mut game_state: ResMut<GameState>,
+) {
+    for (entity, coin, transform) in &coins {
+        if transform.translation.xz().distance(player.translation.xz()) <= COIN_PICKUP_RADIUS {
+            game_state.gold += coin.0;
+            commands.spawn(SamplePlayer::new(assets.pop_sound.clone()));
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn vanish_at_sunrise(
+    mut commands: Commands,
+    coins: Query<Entity, With<Coin>>,
+    game_state: Res<GameState>,
+) {
+    if !game_state.is_sunrise() {
+        return;
+    }
+    for entity in &coins {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,566 @@
+//! Curses: spells cast by wraiths, by the night itself and by the boss. Each curse rolls one or
+//! more effects from [`CURSE_CATALOG`] and warps the screen while it lasts.
+
+use bevy::prelude::*;
+use bevy_rand::{global::GlobalRng, prelude::WyRand};
+use rand::Rng;
+
+use crate::{
+    PausableSystems,
+    crt_postprocess::CrtSettings,
+    game::{
+        GameAssets, GameState, GameStateMachine,
+        level::{Boss, CursedAimState, CursedControls, Enemy, EnemyArchetype, Player},
+        night::{NIGHT_COUNT, night_index},
+    },
+    screens::Screen,
+};
+
+/// Wraiths further away than this don't add to the curse meter.
+const WRAITH_CURSE_RANGE: f32 = 7.0;
+/// Meter filled per second by a single wraith standing on top of the player.
+const WRAITH_CURSE_RATE: f32 = 0.25;
+/// Meter drained per second while no wraiths are close.
+const CURSE_METER_DECAY: f32 = 0.05;
+
+/// The boss curses the player whenever it gets this close, at most once per cooldown.
+const BOSS_CURSE_RANGE: f32 = 10.0;
+const BOSS_CURSE_COOLDOWN: f32 = 12.0;
+
+/// How long the HUD warns before the night casts a curse.
+const CURSE_WARNING_SECONDS: f32 = 3.0;
+/// The HUD also warns once the meter is this full.
+const CURSE_WARNING_METER: f32 = 0.75;
+
+/// How quickly the screen warps in and out, per second.
+const DISTORTION_RATE: f32 = 2.0;
+/// How quickly an upside-down screen turns over, per second.
+const FLIP_RATE: f32 = 1.5;
+
+/// Phantoms kept around the player while they're hallucinating.
+const HALLUCINATION_COUNT: usize = 5;
+const HALLUCINATION_SPEED: f32 = 2.5;
+/// Phantoms vanish once they get this close, and new ones appear further out.
+const HALLUCINATION_POP_DISTANCE: f32 = 1.0;
+
+/// The most a lying clock is off by, in seconds, and how often it changes its story.
+const CLOCK_LIE_SECONDS: f32 = 45.0;
+const CLOCK_LIE_INTERVAL: f32 = 1.5;
+
+/// Something a curse can do. Each curse rolls a few of these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurseEffect {
+    /// Corrupts movement and aim through [`CursedControls`].
+    ScrambledControls,
+    /// The flashlight cuts out at random.
+    FlickeringFlashlight,
+    /// Phantom enemies close in. Light passes straight through them.
+    Hallucinations,
+    /// The screen turns upside down.
+    InvertedScreen,
+    /// Torches rewind their burn and recharge cycles.
+    BackwardsTorches,
+    /// The HUD shows the wrong time.
+    LyingClock,
+}
+
+struct CurseEntry {
+    effect: CurseEffect,
+    /// Shown to the player while the effect lasts.
+    name: &'static str,
+    /// How likely the effect is to roll on each night, relative to the rest. 0 never rolls.
+    weights: [u32; NIGHT_COUNT],
+}
+
+const CURSE_CATALOG: [CurseEntry; 6] = [
+    CurseEntry {
+        effect: CurseEffect::ScrambledControls,
+        name: "Hexed Hands",
+        weights: [10, 10, 8, 8, 8],
+    },
+    CurseEntry {
+        effect: CurseEffect::FlickeringFlashlight,
+        name: "Dying Batteries",
+        weights: [6, 6, 6, 6, 6],
+    },
+    CurseEntry {
+        effect: CurseEffect::Hallucinations,
+        name: "Phantoms",
+        weights: [0, 3, 5, 6, 8],
+    },
+    CurseEntry {
+        effect: CurseEffect::InvertedScreen,
+        name: "Upside Down",
+        weights: [0, 2, 3, 4, 5],
+    },
+    CurseEntry {
+        effect: CurseEffect::BackwardsTorches,
+        name: "Backwards Flames",
+        weights: [0, 2, 4, 5, 6],
+    },
+    CurseEntry {
+        effect: CurseEffect::LyingClock,
+        name: "Lying Clock",
+        weights: [2, 4, 4, 5, 5],
+    },
+];
+
+impl CurseEffect {
+    fn entry(self) -> &'static CurseEntry {
+        CURSE_CATALOG
+            .iter()
+            .find(|entry| entry.effect == self)
+            .expect("every curse effect is in the catalog")
+    }
+
+    pub fn name(self) -> &'static str {
+        self.entry().name
+    }
+}
+
+/// Picks `count` different effects, weighted for the given night.
+fn roll_effects(night_number: usize, count: usize, rng: &mut WyRand) -> Vec<CurseEffect> {
+    let night = night_index(night_number);
+    let mut pool: Vec<&CurseEntry> = CURSE_CATALOG
+        .iter()
+        .filter(|entry| entry.weights[night] > 0)
+        .collect();
+
+    let mut effects = Vec::new();
+    while effects.len() < count && !pool.is_empty() {
+        let total: u32 = pool.iter().map(|entry| entry.weights[night]).sum();
+        let mut pick = rng.random_range(0..total);
+        let index = pool
+            .iter()
+            .position(|entry| {
+                if pick < entry.weights[night] {
+                    return true;
+                }
+                pick -= entry.weights[night];
+                false
+            })
+            .unwrap_or(0);
+        effects.push(pool.swap_remove(index).effect);
+    }
+    effects
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Curse>();
+    app.add_observer(cast_curse);
+    app.add_systems(OnEnter(GameStateMachine::Level), start_night_curses);
+    app.add_systems(OnExit(GameStateMachine::Level), end_curses);
+    app.add_systems(
+        Update,
+        (
+            fill_curse_meter,
+            night_curses,
+            boss_curses,
+            tick_curse,
+            distort_crt,
+            haunt_player,
+            twist_clock,
+        )
+            .chain()
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+}
+
+/// Who cast a curse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurseSource {
+    Wraiths,
+    Night,
+    Boss,
+    /// The dev tools' curse key.
+    #[cfg(feature = "dev")]
+    Dev,
+}
+
+impl CurseSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            CurseSource::Wraiths => "the wraiths",
+            CurseSource::Night => "the night",
+            CurseSource::Boss => "the boss",
+            #[cfg(feature = "dev")]
+            CurseSource::Dev => "dev tools",
+        }
+    }
+
+    fn seconds(self) -> f32 {
+        match self {
+            CurseSource::Wraiths => 8.0,
+            CurseSource::Night => 6.0,
+            CurseSource::Boss => 5.0,
+            #[cfg(feature = "dev")]
+            CurseSource::Dev => 10.0,
+        }
+    }
+}
+
+/// Curses the player, replacing any curse that's already running.
+#[derive(Event)]
+pub struct CastCurse(pub CurseSource);
+
+/// The camera's CRT settings without any curse, restored as a curse wears off.
+#[derive(Default, Clone, Copy)]
+struct CalmCrt {
+    curvature: f32,
+    chromatic_aberration: f32,
+    noise_intensity: f32,
+}
+
+/// A phantom enemy conjured by [`CurseEffect::Hallucinations`].
+#[derive(Component)]
+struct Hallucination;
+
+struct ActiveCurse {
+    source: CurseSource,
+    effects: Vec<CurseEffect>,
+    timer: Timer,
+}
+
+#[derive(Resource)]
+pub struct Curse {
+    /// From 0 to 1. Fills while wraiths are close, and casts a curse when full.
+    pub meter: f32,
+    active: Option<ActiveCurse>,
+    /// Counts down to the night's next curse, on nights that cast them.
+    next_night_curse: Option<Timer>,
+    boss_cooldown: Timer,
+    /// From 0 (calm) to 1 (fully warped).
+    distortion: f32,
+    /// From 0 (upright) to 1 (upside down).
+    flip: f32,
+    calm_crt: CalmCrt,
+    /// Seconds the HUD clock is currently off by.
+    pub clock_lie: f32,
+    clock_lie_timer: Timer,
+}
+
+impl Default for Curse {
+    fn default() -> Self {
+        Self {
+            meter: 0.0,
+            active: None,
+            next_night_curse: None,
+            boss_cooldown: Timer::from_seconds(BOSS_CURSE_COOLDOWN, TimerMode::Once),
+            distortion: 0.0,
+            flip: 0.0,
+            calm_crt: CalmCrt::default(),
+            clock_lie: 0.0,
+            clock_lie_timer: Timer::from_seconds(CLOCK_LIE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Curse {
+    /// Who cast the running curse and how many seconds it has left.
+    pub fn active(&self) -> Option<(CurseSource, f32)> {
+        self.active
+            .as_ref()
+            .map(|active| (active.source, active.timer.remaining_secs()))
+    }
+
+    /// Whether the running curse has rolled `effect`.
+    pub fn has(&self, effect: CurseEffect) -> bool {
+        self.active
+            .as_ref()
+            .is_some_and(|active| active.effects.contains(&effect))
+    }
+
+    /// The running curse's effects, for the HUD.
+    pub fn effects(&self) -> &[CurseEffect] {
+        self.active
+            .as_ref()
+            .map_or(&[], |active| active.effects.as_slice())
+    }
+
+    /// Whether a curse is about to land.
+    pub fn is_looming(&self) -> bool {
+        self.active.is_none()
+            && (self.meter >= CURSE_WARNING_METER
+                || self
+                    .next_night_curse
+                    .as_ref()
+                    .is_some_and(|timer| timer.remaining_secs() <= CURSE_WARNING_SECONDS))
+    }
+
+    fn lift(&mut self, cursed: &mut CursedControls) {
+        if self.active.take().is_some() {
+            info!("Cursed controls lifted.");
+        }
+        cursed.enabled = false;
+    }
+}
+
+/// Dev tools: curse the player, or lift the running curse.
+#[cfg(feature = "dev")]
+pub fn toggle_curse(
+    mut commands: Commands,
+    mut curse: ResMut<Curse>,
+    mut cursed: ResMut<CursedControls>,
+) {
+    if curse.active.is_some() {
+        curse.lift(&mut cursed);
+    } else {
+        commands.trigger(CastCurse(CurseSource::Dev));
+    }
+}
+
+fn start_night_curses(
+    mut curse: ResMut<Curse>,
+    mut cursed: ResMut<CursedControls>,
+    game_state: Res<GameState>,
+    crt: Single<&CrtSettings>,
+) {
+    *curse = Curse {
+        next_night_curse: game_state
+            .night()
+            .curse_interval_seconds
+            .map(|seconds| Timer::from_seconds(seconds, TimerMode::Repeating)),
+        calm_crt: CalmCrt {
+            curvature: crt.curvature,
+            chromatic_aberration: crt.chromatic_aberration,
+            noise_intensity: crt.noise_intensity,
+        },
+        ..default()
+    };
+    cursed.enabled = false;
+}
+
+fn end_curses(
+    mut curse: ResMut<Curse>,
+    mut cursed: ResMut<CursedControls>,
+    mut crt: Single<&mut CrtSettings>,
+) {
+    curse.lift(&mut cursed);
+    curse.distortion = 0.0;
+    curse.flip = 0.0;
+    crt.flip = 0.0;
+    crt.curvature = curse.calm_crt.curvature;
+    crt.chromatic_aberration = curse.calm_crt.chromatic_aberration;
+    crt.noise_intensity = curse.calm_crt.noise_intensity;
+}
+
+fn cast_curse(
+    cast: On<CastCurse>,
+    mut curse: ResMut<Curse>,
+    mut cursed: ResMut<CursedControls>,
+    mut aim_state: ResMut<CursedAimState>,
+    game_state: Res<GameState>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    let effects = roll_effects(
+        game_state.night_number,
+        game_state.night().curse_effects,
+        &mut rng,
+    );
+    info!("Cursed by {}: {:?}", cast.0.name(), effects);
+    cursed.enabled = effects.contains(&CurseEffect::ScrambledControls);
+    curse.active = Some(ActiveCurse {
+        source: cast.0,
+        effects,
+        timer: Timer::from_seconds(cast.0.seconds(), TimerMode::Once),
+    });
+    if !cursed.enabled {
+        return;
+    }
+
+    cursed.speed_mul = rng.random_range(1.4..3.0);
+    cursed.invert = Vec2::new(
+        if rng.random_bool(0.5) { -1.0 } else { 1.0 },
+        if rng.random_bool(0.5) { -1.0 } else { 1.0 },
+    );
+    cursed.skew = Vec2::new(rng.random_range(-1.0..1.0), rng.random_range(-1.0..1.0));
+    cursed.swirl_strength = rng.random_range(0.15..0.75);
+
+    cursed.aim_rotate_rad = rng.random_range(-std::f32::consts::PI..std::f32::consts::PI);
+    cursed.aim_wobble_rad = rng.random_range(0.15..0.9);
+    cursed.aim_wobble_hz = rng.random_range(0.6..3.5);
+    cursed.aim_lag = rng.random_range(0.04..0.22);
+    cursed.aim_jitter = rng.random_range(0.3..2.5);
+
+    aim_state.current_dir = Vec3::ZERO;
+}
+
+fn fill_curse_meter(
+    mut commands: Commands,
+    mut curse: ResMut<Curse>,
+    player: Single<&Transform, With<Player>>,
+    enemies: Query<(&Transform, &EnemyArchetype), With<Enemy>>,
+    time: Res<Time>,
+) {
+    // The meter holds still while a curse runs, so curses don't chain back to back.
+    if curse.active.is_some() {
+        return;
+    }
+
+    let pressure: f32 = enemies
+        .iter()
+        .filter(|(_, archetype)| **archetype == EnemyArchetype::Wraith)
+        .map(|(transform, _)| {
+            let distance = transform.translation.distance(player.translation);
+            (1.0 - distance / WRAITH_CURSE_RANGE).max(0.0)
+        })
+        .sum();
+
+    let delta = if pressure > 0.0 {
+        pressure * WRAITH_CURSE_RATE
+    } else {
+        -CURSE_METER_DECAY
+    };
+    curse.meter = (curse.meter + delta * time.delta_secs()).clamp(0.0, 1.0);
+
+    if curse.meter >= 1.0 {
+        curse.meter = 0.0;
+        commands.trigger(CastCurse(CurseSource::Wraiths));
+    }
+}
+
+fn night_curses(
+    mut commands: Commands,
+    mut curse: ResMut<Curse>,
+    game_state: Res<GameState>,
+    time: Res<Time>,
+) {
+    if game_state.is_sunrise() {
+        return;
+    }
+    let Some(timer) = curse.next_night_curse.as_mut() else {
+        return;
+    };
+    if timer.tick(time.delta()).just_finished() {
+        commands.trigger(CastCurse(CurseSource::Night));
+    }
+}
+
+fn boss_curses(
+    mut commands: Commands,
+    mut curse: ResMut<Curse>,
+    player: Single<&Transform, With<Player>>,
+    bosses: Query<&Transform, With<Boss>>,
+    time: Res<Time>,
+) {
+    curse.boss_cooldown.tick(time.delta());
+    if curse.active.is_some() || !curse.boss_cooldown.is_finished() {
+        return;
+    }
+
+    let in_range = bosses
+        .iter()
+        .any(|boss| boss.translation.distance(player.translation) < BOSS_CURSE_RANGE);
+    if in_range {
+        curse.boss_cooldown.reset();
+        commands.trigger(CastCurse(CurseSource::Boss));
+    }
+}
+
+fn tick_curse(mut curse: ResMut<Curse>, mut cursed: ResMut<CursedControls>, time: Res<Time>) {
+    let Some(active) = curse.active.as_mut() else {
+        return;
+    };
+    if active.timer.tick(time.delta()).is_finished() {
+        curse.lift(&mut cursed);
+    }
+}
+
+/// Warps and smears the picture while a curse runs, easing back once it lifts.
+fn distort_crt(mut curse: ResMut<Curse>, mut crt: Single<&mut CrtSettings>, time: Res<Time>) {
+    let target = if curse.active.is_some() { 1.0 } else { 0.0 };
+    let step = DISTORTION_RATE * time.delta_secs();
+    curse.distortion += (target - curse.distortion).clamp(-step, step);
+
+    let calm = curse.calm_crt;
+    let d = curse.distortion;
+    let pulse = (time.elapsed_secs() * 3.0).sin() * 0.5 + 0.5;
+    crt.curvature = calm.curvature + d * 0.15;
+    crt.chromatic_aberration = calm.chromatic_aberration + d * (0.02 + 0.03 * pulse);
+    crt.noise_intensity = calm.noise_intensity + d * 0.05;
+
+    let target = if curse.has(CurseEffect::InvertedScreen) {
+        1.0
+    } else {
+        0.0
+    };
+    let step = FLIP_RATE * time.delta_secs();
+    curse.flip += (target - curse.flip).clamp(-step, step);
+    // Ease in and out of the turn.
+    crt.flip = curse.flip * curse.flip * (3.0 - 2.0 * curse.flip);
+}
+
+/// Keeps phantoms closing in on the player while they're hallucinating, and banishes them
+/// once the curse lifts. Phantoms aren't enemies, so they can't hurt and can't be burned.
+fn haunt_player(
+    mut commands: Commands,
+    curse: Res<Curse>,
+    assets: Res<GameAssets>,
+    player: Single<&Transform, (With<Player>, Without<Hallucination>)>,
+    mut phantoms: Query<(Entity, &mut Transform), With<Hallucination>>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    time: Res<Time>,
+) {
+    if !curse.has(CurseEffect::Hallucinations) {
+        for (entity, _) in &phantoms {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let target = player.translation.with_y(1.0);
+    let mut count = 0;
+    for (entity, mut transform) in &mut phantoms {
+        let to_player = target - transform.translation;
+        if to_player.length() < HALLUCINATION_POP_DISTANCE {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += to_player.normalize() * HALLUCINATION_SPEED * time.delta_secs();
+        transform.look_at(target, Vec3::Y);
+        count += 1;
+    }
+
+    for _ in count..HALLUCINATION_COUNT {
+        let theta = rng.random_range(0.0..std::f32::consts::TAU);
+        let radius = rng.random_range(8.0..14.0);
+        let position = target + vec3(theta.cos(), 0.0, theta.sin()) * radius;
+        let vox = match rng.random_range(1..6) {
+            1 => assets.vox1.clone(),
+            2 => assets.vox2.clone(),
+            3 => assets.vox3.clone(),
+            4 => assets.vox4.clone(),
+            _ => assets.vox5.clone(),
+        };
+        commands.spawn((
+            Name::new("Hallucination"),
+            Hallucination,
+            DespawnOnExit(GameStateMachine::Level),
+            DespawnOnExit(Screen::Gameplay),
+            Transform::from_translation(position).looking_at(target, Vec3::Y),
+            Visibility::default(),
+            children![(
+                Transform::from_scale(vec3(0.25, 0.12, 0.25))
+                    .with_translation(vec3(-1., -1., -0.5)),
+                Visibility::default(),
+                children![SceneRoot(vox)],
+            )],
+        ));
+    }
+}
+
+/// Makes up a new time every so often while the clock is lying.
+fn twist_clock(
+    mut curse: ResMut<Curse>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    time: Res<Time>,
+) {
+    if !curse.has(CurseEffect::LyingClock) {
+        curse.clock_lie = 0.0;
+        return;
+    }
+    if curse.clock_lie_timer.tick(time.delta()).just_finished() || curse.clock_lie == 0.0 {
+        curse.clock_lie = rng.random_range(-CLOCK_LIE_SECONDS..CLOCK_LIE_SECONDS);
+    }
+}
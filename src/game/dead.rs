@@ -1,16 +1,128 @@
+//! Losing a night: a slow-motion collapse, then the Dead screen saying what went wrong.
+
 use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
 
 use crate::{
-    game::{GameState, GameStateMachine},
+    IsometricCamera, PausableSystems,
+    crt_postprocess::CrtSettings,
+    game::{GameState, GameStateMachine, level::Player},
     screens::Screen,
     theme::widget,
 };
 
+/// How fast the game runs while the player goes down.
+const DEATH_TIME_SCALE: f32 = 0.2;
+/// Real seconds from the killing blow until the Dead screen.
+const DEATH_SEQUENCE_SECONDS: f32 = 2.5;
+/// How far the camera closes in on the player, as a fraction of its usual view.
+const DEATH_ZOOM: f32 = 0.4;
+/// Real seconds for the Dead screen to fade in from black.
+const DEAD_FADE_SECONDS: f32 = 1.0;
+
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DeathSequence>();
+    app.init_resource::<LastLoss>();
+    app.add_observer(start_death_sequence);
+    app.add_systems(
+        Update,
+        play_death_sequence
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+    app.add_systems(OnExit(GameStateMachine::Level), end_death_sequence);
     app.add_systems(OnEnter(GameStateMachine::Dead), spawn_dead);
+    app.add_systems(
+        Update,
+        fade_in_dead.run_if(in_state(GameStateMachine::Dead)),
+    );
+}
+
+/// The night is lost, for the given reason.
+#[derive(Event)]
+pub(super) struct NightLost(pub LossCause);
+
+/// Why a night was lost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum LossCause {
+    /// The player's health ran out, and no last stand saved them.
+    #[default]
+    Slain,
+    /// The night's objective can't be completed any more.
+    ObjectiveFailed,
+}
+
+/// How the last night was lost, for the Dead screen.
+#[derive(Resource, Default)]
+struct LastLoss(LossCause);
+
+/// Counts down, in real time, while the player goes down.
+#[derive(Resource, Default)]
+struct DeathSequence(Option<Timer>);
+
+/// The black cover the Dead screen fades in from.
+#[derive(Component)]
+struct DeadFade(Timer);
+
+fn start_death_sequence(
+    lost: On<NightLost>,
+    mut commands: Commands,
+    mut sequence: ResMut<DeathSequence>,
+    mut last_loss: ResMut<LastLoss>,
+    mut time: ResMut<Time<Virtual>>,
+    player: Single<Entity, With<Player>>,
+) {
+    last_loss.0 = lost.0;
+    sequence.0 = Some(Timer::from_seconds(DEATH_SEQUENCE_SECONDS, TimerMode::Once));
+    time.set_relative_speed(DEATH_TIME_SCALE);
+    // The dead don't walk, aim or dash.
+    commands
+        .entity(*player)
+        .despawn_related::<Actions<Player>>();
+}
+
+/// Collapses the CRT and zooms the camera in on the player, then shows the Dead screen.
+fn play_death_sequence(
+    mut sequence: ResMut<DeathSequence>,
+    mut camera: Single<(&mut Projection, &mut CrtSettings), With<IsometricCamera>>,
+    real_time: Res<Time<Real>>,
+    mut next_state: ResMut<NextState<GameStateMachine>>,
+) {
+    let Some(timer) = sequence.0.as_mut() else {
+        return;
+    };
+    timer.tick(real_time.delta());
+    let t = timer.fraction();
+    // Hold the picture for the first part so the killing blow lands, then switch it off.
+    let collapse = ((t - 0.4) / 0.6).clamp(0.0, 1.0);
+    let zoom = 1.0 - (1.0 - t).powi(3);
+
+    let (ref mut projection, ref mut crt) = *camera;
+    if let Projection::Orthographic(orthographic) = &mut **projection {
+        orthographic.scale = 1.0 + (DEATH_ZOOM - 1.0) * zoom;
+    }
+    crt.collapse = collapse;
+
+    if timer.is_finished() {
+        next_state.set(GameStateMachine::Dead);
+    }
+}
+
+fn end_death_sequence(
+    mut sequence: ResMut<DeathSequence>,
+    mut time: ResMut<Time<Virtual>>,
+    mut camera: Single<(&mut Projection, &mut CrtSettings), With<IsometricCamera>>,
+) {
+    sequence.0 = None;
+    time.set_relative_speed(1.0);
+    let (ref mut projection, ref mut crt) = *camera;
+    if let Projection::Orthographic(orthographic) = &mut **projection {
+        orthographic.scale = 1.0;
+    }
+    crt.collapse = 0.0;
 }
 
-fn spawn_dead(mut commands: Commands, game_state: ResMut<GameState>) {
+fn spawn_dead(mut commands: Commands, game_state: ResMut<GameState>, last_loss: Res<LastLoss>) {
     let minutes = (game_state.survived_seconds_this_night % 3600.) / 60.;
     let seconds = game_state.survived_seconds_this_night % 60.;
     commands.spawn((
@@ -19,8 +131,15 @@ fn spawn_dead(mut commands: Commands, game_state: ResMut<GameState>) {
         DespawnOnExit(GameStateMachine::Dead),
         DespawnOnExit(Screen::Gameplay),
         children![
-            widget::header("You didn't survive!"),
-            widget::label("Can you last until sunrise (2.5 minutes)?"),
+            widget::header(match last_loss.0 {
+                LossCause::Slain => "You didn't survive!",
+                LossCause::ObjectiveFailed => "You failed the objective!",
+            }),
+            widget::label(format!(
+                "{} Sunrise comes after {:.0} seconds.",
+                game_state.objective(game_state.night_number).description(),
+                game_state.night().duration_seconds
+            )),
             widget::label(""),
             widget::label(format!("Kills: {}", game_state.kills_this_night)),
             widget::label(format!(
@@ -32,6 +151,34 @@ fn spawn_dead(mut commands: Commands, game_state: ResMut<GameState>) {
             widget::button("Shop", go_to_shop),
         ],
     ));
+    commands.spawn((
+        Name::new("Dead Fade"),
+        GlobalZIndex(2),
+        DespawnOnExit(GameStateMachine::Dead),
+        DespawnOnExit(Screen::Gameplay),
+        DeadFade(Timer::from_seconds(DEAD_FADE_SECONDS, TimerMode::Once)),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            height: percent(100),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        Pickable::IGNORE,
+    ));
+}
+
+fn fade_in_dead(
+    mut commands: Commands,
+    mut fade: Single<(Entity, &mut DeadFade, &mut BackgroundColor)>,
+    real_time: Res<Time<Real>>,
+) {
+    let (entity, ref mut timer, ref mut color) = *fade;
+    timer.0.tick(real_time.delta());
+    color.0 = Color::BLACK.with_alpha(timer.0.fraction_remaining());
+    if timer.0.is_finished() {
+        commands.entity(entity).despawn();
+    }
 }
 
 fn go_to_shop(_: On<Pointer<Click>>, mut state: ResMut<NextState<GameStateMachine>>) {
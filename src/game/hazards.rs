@@ -0,0 +1,91 @@
+//! Dark zones and fog banks laid out in the level file. Thick darkness swallows the ambient
+//! light, shortens the flashlight and slowly drains the player's health. Fog shortens the
+//! flashlight and hides whatever is standing in it.
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    game::{
+        GameAssets, GameStateMachine,
+        level::Player,
+        level_file::{HazardKind, LevelFile},
+    },
+};
+
+/// Health lost per second standing in a dark zone, before damage resistance.
+pub(super) const DARK_ZONE_DAMAGE_PER_SECOND: f32 = 4.0;
+/// How much of the ambient light a dark zone takes away.
+const DARK_AMBIENT_LOSS: f32 = 0.9;
+/// How much of the flashlight's reach a dark zone takes away.
+const DARK_FLASHLIGHT_LOSS: f32 = 0.4;
+/// How much of the flashlight's reach fog takes away.
+const FOG_FLASHLIGHT_LOSS: f32 = 0.35;
+/// Seconds for the light to settle after stepping into or out of a hazard.
+const EXPOSURE_FADE_SECONDS: f32 = 0.75;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<HazardExposure>();
+    app.add_systems(OnEnter(GameStateMachine::Level), reset_exposure);
+    app.add_systems(
+        Update,
+        track_exposure
+            .run_if(resource_exists::<GameAssets>)
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+}
+
+/// How deep in the level's hazards the player is standing.
+#[derive(Resource, Default)]
+pub(super) struct HazardExposure {
+    /// Whether the player is inside a dark zone right now.
+    pub in_dark: bool,
+    /// Eases from 0 to 1 as the player walks into a dark zone.
+    dark: f32,
+    /// Eases from 0 to 1 as the player walks into fog.
+    fog: f32,
+}
+
+impl HazardExposure {
+    /// What's left of the ambient light.
+    pub fn ambient_factor(&self) -> f32 {
+        1.0 - DARK_AMBIENT_LOSS * self.dark
+    }
+
+    /// What's left of the flashlight's reach.
+    pub fn flashlight_factor(&self) -> f32 {
+        (1.0 - DARK_FLASHLIGHT_LOSS * self.dark) * (1.0 - FOG_FLASHLIGHT_LOSS * self.fog)
+    }
+}
+
+fn reset_exposure(mut exposure: ResMut<HazardExposure>) {
+    *exposure = HazardExposure::default();
+}
+
+fn track_exposure(
+    mut exposure: ResMut<HazardExposure>,
+    player: Single<&Transform, With<Player>>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    time: Res<Time>,
+) {
+    let inside = |kind| {
+        assets
+            .level(&levels)
+            .hazards
+            .iter()
+            .any(|hazard| hazard.kind == kind && hazard.contains(player.translation))
+    };
+    let in_dark = inside(HazardKind::Dark);
+    let in_fog = inside(HazardKind::Fog);
+
+    let step = time.delta_secs() / EXPOSURE_FADE_SECONDS;
+    let fade = |value: f32, inside: bool| {
+        let target = if inside { 1.0 } else { 0.0 };
+        value + (target - value).clamp(-step, step)
+    };
+    exposure.in_dark = in_dark;
+    exposure.dark = fade(exposure.dark, in_dark);
+    exposure.fog = fade(exposure.fog, in_fog);
+}
@@ -1,7 +1,12 @@
-use bevy::prelude::*;
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
 
 use crate::{
-    game::{GameState, GameStateMachine},
+    game::{
+        GameState, GameStateMachine, TorchKind,
+        curse::Curse,
+        level::{DashState, Health, Player, Stamina, TORCH_COLOR, TorchCharge},
+        objectives::ActiveObjective,
+    },
     screens::Screen,
     theme::widget,
 };
@@ -12,12 +17,237 @@ struct TimeUI;
 #[derive(Component)]
 struct KillsUI;
 
+/// The night's objective and how far along it is.
+#[derive(Component)]
+struct ObjectiveUI;
+
+/// Radial charge display for the manual torch at this index in `GameState::torches`.
+#[derive(Component)]
+struct TorchChargeIndicator(usize);
+
+/// One dot of a [`TorchChargeIndicator`]'s ring, counted clockwise from the top.
+#[derive(Component)]
+struct ChargeSegment(usize);
+
+/// Warns about curses and counts down the running one.
+#[derive(Component)]
+struct CurseUI;
+
+/// The filled part of the curse meter.
+#[derive(Component)]
+struct CurseMeterFill;
+
+const CURSE_COLOR: Color = Color::srgb(0.7, 0.2, 1.0);
+
+/// The filled part of the health bar.
+#[derive(Component)]
+struct HealthFill;
+
+/// Current and max health, next to the bar.
+#[derive(Component)]
+struct HealthText;
+
+const HEALTH_BAR_WIDTH: f32 = 200.0;
+const HEALTH_BAR_HEIGHT: f32 = 14.0;
+const HEALTH_COLOR: Color = Color::srgb(0.8, 0.15, 0.15);
+
+/// The filled part of the dash cooldown bar.
+#[derive(Component)]
+struct DashCooldownFill;
+
+/// The filled part of the stamina bar.
+#[derive(Component)]
+struct StaminaFill;
+
+const STAMINA_COLOR: Color = Color::srgb(0.3, 0.8, 0.4);
+const STAMINA_EXHAUSTED_COLOR: Color = Color::srgb(0.6, 0.5, 0.2);
+
+const DASH_BAR_WIDTH: f32 = 120.0;
+const DASH_BAR_HEIGHT: f32 = 10.0;
+const DASH_READY_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const DASH_CHARGING_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+const CHARGE_SEGMENTS: usize = 12;
+const CHARGE_RING_SIZE: f32 = 48.0;
+const CHARGE_DOT_SIZE: f32 = 8.0;
+const CHARGE_EMPTY_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(GameStateMachine::Level), spawn_hud);
-    app.add_systems(Update, update_hud);
+    app.add_systems(
+        Update,
+        (
+            update_hud,
+            update_torch_charge,
+            update_dash_cooldown,
+            update_stamina,
+            update_health,
+            update_curse,
+        ),
+    );
 }
 
-fn spawn_hud(mut commands: Commands) {
+fn spawn_hud(mut commands: Commands, game_state: Res<GameState>) {
+    let manual_torches: Vec<usize> = game_state
+        .torches
+        .iter()
+        .enumerate()
+        .filter(|(_, torch)| torch.kind == TorchKind::Manual)
+        .map(|(slot, _)| slot)
+        .collect();
+
+    commands.spawn((
+        GlobalZIndex(1),
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        Name::new("Torch Charges"),
+        Node {
+            position_type: PositionType::Absolute,
+            right: px(20),
+            bottom: px(20),
+            column_gap: px(10),
+            ..default()
+        },
+        Pickable::IGNORE,
+        Children::spawn(SpawnIter(manual_torches.into_iter().map(torch_charge_ring))),
+    ));
+
+    commands.spawn((
+        GlobalZIndex(1),
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        Name::new("Curse"),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            top: px(20),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: px(4),
+            ..default()
+        },
+        Pickable::IGNORE,
+        children![
+            (CurseUI, widget::header(""), TextColor(CURSE_COLOR)),
+            (
+                Node {
+                    width: px(DASH_BAR_WIDTH),
+                    height: px(DASH_BAR_HEIGHT),
+                    border_radius: BorderRadius::MAX,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BackgroundColor(CHARGE_EMPTY_COLOR),
+                children![(
+                    CurseMeterFill,
+                    Node {
+                        width: percent(0),
+                        height: percent(100),
+                        ..default()
+                    },
+                    BackgroundColor(CURSE_COLOR),
+                )],
+            ),
+        ],
+    ));
+
+    commands.spawn((
+        GlobalZIndex(1),
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        Name::new("Health"),
+        Node {
+            position_type: PositionType::Absolute,
+            left: px(20),
+            top: px(20),
+            align_items: AlignItems::Center,
+            column_gap: px(10),
+            ..default()
+        },
+        Pickable::IGNORE,
+        children![
+            (
+                Node {
+                    width: px(HEALTH_BAR_WIDTH),
+                    height: px(HEALTH_BAR_HEIGHT),
+                    border_radius: BorderRadius::MAX,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BackgroundColor(CHARGE_EMPTY_COLOR),
+                children![(
+                    HealthFill,
+                    Node {
+                        width: percent(100),
+                        height: percent(100),
+                        ..default()
+                    },
+                    BackgroundColor(HEALTH_COLOR),
+                )],
+            ),
+            (HealthText, widget::label("")),
+        ],
+    ));
+
+    commands.spawn((
+        GlobalZIndex(1),
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        Name::new("Dash and Stamina"),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            bottom: px(20),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: px(4),
+            ..default()
+        },
+        Pickable::IGNORE,
+        children![
+            widget::label("Stamina"),
+            (
+                Node {
+                    width: px(DASH_BAR_WIDTH),
+                    height: px(DASH_BAR_HEIGHT),
+                    border_radius: BorderRadius::MAX,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BackgroundColor(CHARGE_EMPTY_COLOR),
+                children![(
+                    StaminaFill,
+                    Node {
+                        width: percent(100),
+                        height: percent(100),
+                        ..default()
+                    },
+                    BackgroundColor(STAMINA_COLOR),
+                )],
+            ),
+            widget::label("Dash"),
+            (
+                Node {
+                    width: px(DASH_BAR_WIDTH),
+                    height: px(DASH_BAR_HEIGHT),
+                    border_radius: BorderRadius::MAX,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BackgroundColor(CHARGE_EMPTY_COLOR),
+                children![(
+                    DashCooldownFill,
+                    Node {
+                        width: percent(100),
+                        height: percent(100),
+                        ..default()
+                    },
+                    BackgroundColor(DASH_READY_COLOR),
+                )],
+            ),
+        ],
+    ));
+
     commands.spawn((
         GlobalZIndex(1),
         DespawnOnExit(GameStateMachine::Level),
@@ -31,17 +261,154 @@ fn spawn_hud(mut commands: Commands) {
             ..default()
         },
         Pickable::IGNORE,
-        children![(TimeUI, widget::header("")), (KillsUI, widget::header(""))],
+        children![
+            (TimeUI, widget::header("")),
+            (KillsUI, widget::header("")),
+            (ObjectiveUI, widget::header("")),
+        ],
     ));
 }
 
 fn update_hud(
     mut time: Single<&mut Text, With<TimeUI>>,
     mut kills: Single<&mut Text, (With<KillsUI>, Without<TimeUI>)>,
+    mut objective_text: Single<&mut Text, (With<ObjectiveUI>, Without<TimeUI>, Without<KillsUI>)>,
     game_state: Res<GameState>,
+    curse: Res<Curse>,
+    objective: Res<ActiveObjective>,
 ) {
-    let minutes = (game_state.survived_seconds_this_night % 3600.) / 60.;
-    let seconds = game_state.survived_seconds_this_night % 60.;
+    // A lying clock is only as convincing as it is plausible, so it never goes below zero.
+    let survived = (game_state.survived_seconds_this_night + curse.clock_lie).max(0.0);
+    let minutes = (survived % 3600.) / 60.;
+    let seconds = survived % 60.;
     **time = format!("Time: {:02}:{:02}", minutes.floor(), seconds.floor()).into();
-    **kills = format!("Kills: {}", game_state.kills_this_night).into();
+    **kills = format!(
+        "Kills: {}  Gold: {}g",
+        game_state.kills_this_night,
+        game_state.gold - game_state.spent
+    )
+    .into();
+    **objective_text = objective.progress(game_state.is_sunrise()).into();
+}
+
+fn torch_charge_ring(slot: usize) -> impl Bundle {
+    (
+        Name::new("Torch Charge"),
+        TorchChargeIndicator(slot),
+        Node {
+            width: px(CHARGE_RING_SIZE),
+            height: px(CHARGE_RING_SIZE),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Children::spawn((
+            Spawn(widget::label(format!("{}", slot + 1))),
+            SpawnIter((0..CHARGE_SEGMENTS).map(|i| {
+                let angle = i as f32 / CHARGE_SEGMENTS as f32 * std::f32::consts::TAU;
+                let radius = (CHARGE_RING_SIZE - CHARGE_DOT_SIZE) / 2.0;
+                let center = CHARGE_RING_SIZE / 2.0 - CHARGE_DOT_SIZE / 2.0;
+                (
+                    ChargeSegment(i),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: px(center + radius * angle.sin()),
+                        top: px(center - radius * angle.cos()),
+                        width: px(CHARGE_DOT_SIZE),
+                        height: px(CHARGE_DOT_SIZE),
+                        border_radius: BorderRadius::MAX,
+                        ..default()
+                    },
+                    BackgroundColor(CHARGE_EMPTY_COLOR),
+                )
+            })),
+        )),
+    )
+}
+
+fn update_torch_charge(
+    torches: Query<&TorchCharge>,
+    indicators: Query<(&TorchChargeIndicator, &Children)>,
+    mut segments: Query<(&ChargeSegment, &mut BackgroundColor)>,
+) {
+    for (indicator, children) in &indicators {
+        let Some(torch) = torches.iter().find(|torch| torch.slot == indicator.0) else {
+            continue;
+        };
+        let lit = (torch.charge * CHARGE_SEGMENTS as f32).round() as usize;
+        for child in children {
+            if let Ok((segment, mut color)) = segments.get_mut(*child) {
+                color.0 = if segment.0 < lit {
+                    TORCH_COLOR
+                } else {
+                    CHARGE_EMPTY_COLOR
+                };
+            }
+        }
+    }
+}
+
+fn update_dash_cooldown(
+    dash: Single<&DashState>,
+    mut fill: Single<(&mut Node, &mut BackgroundColor), With<DashCooldownFill>>,
+) {
+    let readiness = dash.readiness();
+    let (ref mut node, ref mut color) = *fill;
+    node.width = percent(readiness * 100.0);
+    color.0 = if readiness >= 1.0 {
+        DASH_READY_COLOR
+    } else {
+        DASH_CHARGING_COLOR
+    };
+}
+
+fn update_stamina(
+    stamina: Single<&Stamina>,
+    mut fill: Single<(&mut Node, &mut BackgroundColor), With<StaminaFill>>,
+) {
+    let (ref mut node, ref mut color) = *fill;
+    node.width = percent(stamina.current * 100.0);
+    color.0 = if stamina.is_exhausted() {
+        STAMINA_EXHAUSTED_COLOR
+    } else {
+        STAMINA_COLOR
+    };
+}
+
+fn update_health(
+    player: Single<&Health, With<Player>>,
+    game_state: Res<GameState>,
+    mut fill: Single<&mut Node, With<HealthFill>>,
+    mut text: Single<&mut Text, With<HealthText>>,
+) {
+    let max_health = game_state.player.max_health;
+    fill.width = percent((player.0 / max_health).clamp(0.0, 1.0) * 100.0);
+    **text = format!("{:.0}/{:.0}", player.0.ceil(), max_health).into();
+}
+
+fn update_curse(
+    curse: Res<Curse>,
+    time: Res<Time>,
+    mut text: Single<(&mut Text, &mut TextColor), With<CurseUI>>,
+    mut meter: Single<&mut Node, With<CurseMeterFill>>,
+) {
+    let (ref mut text, ref mut color) = *text;
+    if let Some((source, seconds)) = curse.active() {
+        let effects: Vec<&str> = curse.effects().iter().map(|effect| effect.name()).collect();
+        **text = format!(
+            "Cursed by {}: {} {:.1}s",
+            source.name(),
+            effects.join(", "),
+            seconds
+        )
+        .into();
+        color.0 = CURSE_COLOR;
+    } else if curse.is_looming() {
+        **text = "A curse is gathering...".into();
+        // Flicker so the warning catches the eye.
+        color.0 = CURSE_COLOR.with_alpha(0.5 + 0.5 * (time.elapsed_secs() * 8.0).sin().abs());
+    } else {
+        **text = "".into();
+    }
+    meter.width = percent(curse.meter * 100.0);
 }
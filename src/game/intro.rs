@@ -14,6 +14,8 @@ fn spawn_intro(mut commands: Commands, mut game_state: ResMut<GameState>) {
     let quotes_len = game_state.quotes.len();
     let (quote, author) = game_state.quotes[game_state.current_quote_index].clone();
     game_state.current_quote_index = (game_state.current_quote_index + 1) % quotes_len;
+    let biome = game_state.biome(game_state.night_number);
+    let objective = game_state.objective(game_state.night_number);
 
     commands.spawn((
         widget::ui_root("Main Menu"),
@@ -22,6 +24,9 @@ fn spawn_intro(mut commands: Commands, mut game_state: ResMut<GameState>) {
         DespawnOnExit(Screen::Gameplay),
         children![
             widget::header(format!("Night #{:}", game_state.night_number)),
+            widget::label(biome.name()),
+            widget::label(biome.description()),
+            widget::label(objective.description()),
             widget::label(quote),
             widget::label(author),
             widget::button("Start", go_to_level),
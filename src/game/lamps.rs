@@ -0,0 +1,285 @@
+//! Street lamps standing around the arena. They start every night broken, and the player
+//! repairs one by standing next to it for a few seconds. A repaired lamp burns enemies like a
+//! torch until a lamp smasher knocks it out again.
+
+use bevy::prelude::*;
+use bevy_rand::{global::GlobalRng, prelude::WyRand};
+use bevy_rapier3d::prelude::*;
+use bevy_seedling::sample::SamplePlayer;
+use rand::Rng;
+
+use crate::{
+    PausableSystems,
+    game::{
+        GameAssets, GameState, GameStateMachine,
+        arena::TORCH_CLEARANCE,
+        level::{ARENA_SIZE, Enemy, EnemyArchetype, Player, TORCH_COLOR, spawn_enemy},
+        level_file::LevelFile,
+    },
+    screens::Screen,
+};
+
+/// How close the player has to stand to work on a lamp.
+const REPAIR_RADIUS: f32 = 2.0;
+/// Seconds of standing next to a broken lamp to get it going.
+const REPAIR_SECONDS: f32 = 3.0;
+/// How far a repaired lamp's light burns enemies.
+pub(super) const STREET_LAMP_RANGE: f32 = 6.0;
+const STREET_LAMP_INTENSITY: f32 = 100000.0;
+
+/// How often a lamp smasher comes for the lit lamps.
+const SMASHER_INTERVAL_SECONDS: f32 = 25.0;
+const MAX_SMASHERS: usize = 2;
+/// Tough enough to reach a lamp through its light, but not the flashlight as well.
+const SMASHER_HEALTH: f32 = 150.0;
+const SMASHER_SPEED: f32 = 3.5;
+/// How far from its lamp a smasher turns up.
+const SMASHER_SPAWN_DISTANCE: f32 = 18.0;
+/// How close a smasher has to get to knock a lamp out.
+const SMASH_DISTANCE: f32 = 1.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SmasherSpawner>();
+    app.add_systems(
+        OnEnter(GameStateMachine::Level),
+        (spawn_street_lamps, start_smasher_spawner),
+    );
+    app.add_systems(
+        Update,
+        (
+            repair_lamps,
+            spawn_smashers,
+            target_lamps,
+            smash_lamps,
+            show_lamp_light,
+        )
+            .chain()
+            .run_if(resource_exists::<GameAssets>)
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+}
+
+#[derive(Component)]
+pub(super) struct StreetLamp {
+    /// Repair progress, from 0 (broken) to 1 (lit).
+    repair: f32,
+}
+
+impl StreetLamp {
+    pub fn is_lit(&self) -> bool {
+        self.repair >= 1.0
+    }
+}
+
+#[derive(Component)]
+struct StreetLampLight;
+
+/// An enemy that goes for the lit lamps instead of the player. Light slows it no more than
+/// it slows the boss.
+#[derive(Component, Default)]
+pub(super) struct LampSmasher {
+    /// The lamp it's headed for and where that lamp stands. `None` while every lamp is dark,
+    /// in which case it chases the player like any other enemy.
+    pub target: Option<(Entity, Vec3)>,
+}
+
+/// Counts down to the next lamp smasher.
+#[derive(Resource, Default)]
+struct SmasherSpawner(Timer);
+
+fn start_smasher_spawner(mut spawner: ResMut<SmasherSpawner>) {
+    spawner.0 = Timer::from_seconds(SMASHER_INTERVAL_SECONDS, TimerMode::Repeating);
+}
+
+fn spawn_street_lamps(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    game_state: Res<GameState>,
+) {
+    let level = assets.level(&levels);
+    let layout = game_state.arena_layout(game_state.night_number, level);
+    for &position in &level.street_lamps {
+        // The seeded pieces don't make way for lamps, so a lamp they cover sits the night out.
+        if !layout.is_clear(position, TORCH_CLEARANCE) {
+            debug!("Skipping the street lamp at {position}, a piece stands on it tonight");
+            continue;
+        }
+        commands.spawn(street_lamp(&assets, position, false));
+    }
+}
+
+/// A street lamp at `position`, either broken or already `lit`.
+pub(super) fn street_lamp(assets: &GameAssets, position: Vec3, lit: bool) -> impl Bundle {
+    (
+        Name::new("Street Lamp"),
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        StreetLamp {
+            repair: if lit { 1.0 } else { 0.0 },
+        },
+        Visibility::default(),
+        Transform::from_translation(position),
+        RigidBody::Fixed,
+        Collider::cuboid(0.5, 0.5, 0.5),
+        children![
+            (
+                Visibility::default(),
+                SceneRoot(assets.lamp.clone()),
+                Transform::from_scale(vec3(0.25, 0.3, 0.25)),
+            ),
+            (
+                StreetLampLight,
+                Visibility::Hidden,
+                Transform::from_xyz(0.0, 3.0, 0.0),
+                PointLight {
+                    color: TORCH_COLOR,
+                    intensity: 0.0,
+                    range: STREET_LAMP_RANGE + 3.0,
+                    radius: std::f32::consts::PI,
+                    ..default()
+                },
+            ),
+        ],
+    )
+}
+
+/// Standing by a broken lamp fixes it bit by bit. Walking off lets the progress slip back.
+fn repair_lamps(
+    mut commands: Commands,
+    mut lamps: Query<(&mut StreetLamp, &GlobalTransform)>,
+    player: Single<&Transform, With<Player>>,
+    assets: Res<GameAssets>,
+    time: Res<Time>,
+) {
+    let step = time.delta_secs() / REPAIR_SECONDS;
+    for (mut lamp, transform) in &mut lamps {
+        if lamp.is_lit() {
+            continue;
+        }
+        let distance = transform
+            .translation()
+            .xz()
+            .distance(player.translation.xz());
+        if distance <= REPAIR_RADIUS {
+            lamp.repair = (lamp.repair + step).min(1.0);
+            if lamp.is_lit() {
+                commands.spawn(SamplePlayer::new(assets.pop_sound.clone()));
+            }
+        } else {
+            lamp.repair = (lamp.repair - step).max(0.0);
+        }
+    }
+}
+
+fn spawn_smashers(
+    mut commands: Commands,
+    mut spawner: ResMut<SmasherSpawner>,
+    assets: Res<GameAssets>,
+    game_state: Res<GameState>,
+    lamps: Query<(&StreetLamp, &GlobalTransform)>,
+    smashers: Query<(), With<LampSmasher>>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    time: Res<Time>,
+) {
+    if game_state.is_sunrise() || !spawner.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    if smashers.iter().count() >= MAX_SMASHERS {
+        return;
+    }
+    let lit: Vec<Vec3> = lamps
+        .iter()
+        .filter(|(lamp, _)| lamp.is_lit())
+        .map(|(_, transform)| transform.translation())
+        .collect();
+    if lit.is_empty() {
+        return;
+    }
+
+    let lamp = lit[rng.random_range(0..lit.len())];
+    let angle = rng.random_range(0.0..std::f32::consts::TAU);
+    let limit = ARENA_SIZE / 2.0 - 1.0;
+    let position = (lamp + Quat::from_rotation_y(angle) * Vec3::X * SMASHER_SPAWN_DISTANCE)
+        .clamp(Vec3::splat(-limit), Vec3::splat(limit));
+    let smasher = spawn_enemy(
+        &mut commands,
+        position.x,
+        position.z,
+        assets.vox4.clone(),
+        EnemyArchetype::Brute,
+        SMASHER_SPEED,
+        SMASHER_HEALTH,
+    );
+    commands
+        .entity(smasher)
+        .insert((Name::new("Lamp Smasher"), LampSmasher::default()));
+}
+
+/// Points every smasher at the nearest lit lamp.
+fn target_lamps(
+    mut smashers: Query<(&mut LampSmasher, &Transform), With<Enemy>>,
+    lamps: Query<(Entity, &StreetLamp, &GlobalTransform)>,
+) {
+    for (mut smasher, transform) in &mut smashers {
+        smasher.target = lamps
+            .iter()
+            .filter(|(_, lamp, _)| lamp.is_lit())
+            .map(|(entity, _, lamp_transform)| (entity, lamp_transform.translation()))
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(transform.translation)
+                    .total_cmp(&b.distance_squared(transform.translation))
+            });
+    }
+}
+
+fn smash_lamps(
+    mut commands: Commands,
+    smashers: Query<(&LampSmasher, &Transform)>,
+    mut lamps: Query<&mut StreetLamp>,
+    assets: Res<GameAssets>,
+) {
+    for (smasher, transform) in &smashers {
+        let Some((target, position)) = smasher.target else {
+            continue;
+        };
+        if position.xz().distance(transform.translation.xz()) > SMASH_DISTANCE {
+            continue;
+        }
+        let Ok(mut lamp) = lamps.get_mut(target) else {
+            continue;
+        };
+        if lamp.is_lit() {
+            lamp.repair = 0.0;
+            commands.spawn(SamplePlayer::new(assets.pop_sound.clone()));
+        }
+    }
+}
+
+/// Lit lamps shine fully. A lamp being repaired flickers up as it comes back to life.
+fn show_lamp_light(
+    lamps: Query<(&StreetLamp, &Children)>,
+    mut lights: Query<(&mut PointLight, &mut Visibility), With<StreetLampLight>>,
+    time: Res<Time>,
+) {
+    for (lamp, children) in &lamps {
+        for &child in children {
+            let Ok((mut light, mut visibility)) = lights.get_mut(child) else {
+                continue;
+            };
+            let brightness = if lamp.is_lit() {
+                1.0
+            } else {
+                let flicker = (time.elapsed_secs() * 20.0).sin() * 0.5 + 0.5;
+                lamp.repair * 0.3 * flicker
+            };
+            light.intensity = STREET_LAMP_INTENSITY * brightness;
+            visibility.set_if_neq(if brightness > 0.0 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+    }
+}
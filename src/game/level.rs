@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_enhanced_input::prelude::*;
@@ -11,7 +11,19 @@ use rand::Rng;
 use crate::{
     IsometricCamera, PausableSystems,
     crt_postprocess::CrtSettings,
-    game::{GameAssets, GameState, GameStateMachine, LIGHT_COLOR},
+    game::{
+        GameAssets, GameState, GameStateMachine, LIGHT_COLOR, LightHue, TorchKind,
+        arena::{ArenaLayout, PieceKind},
+        barricades::Besieging,
+        curse::{Curse, CurseEffect},
+        dead::{LossCause, NightLost},
+        hazards::{DARK_ZONE_DAMAGE_PER_SECOND, HazardExposure},
+        lamps::{LampSmasher, STREET_LAMP_RANGE, StreetLamp},
+        level_file::{HazardKind, LevelFile, LevelGeometry},
+        objectives::ActiveObjective,
+        pickups::warm_lamp,
+    },
+    keybindings::{GameAction, Keybindings},
     screens::Screen,
 };
 
@@ -24,21 +36,36 @@ pub(super) fn plugin(app: &mut App) {
     // Cursed controls
     app.init_resource::<CursedControls>();
     app.init_resource::<CursedAimState>();
+    app.init_resource::<AimInput>();
 
     // Input observer
     app.add_plugins(EnhancedInputPlugin);
     app.add_input_context::<Player>();
     app.add_observer(apply_movement);
+    app.add_observer(record_directional_aim);
+    app.add_observer(ignite_torch);
+    app.add_observer(cycle_lens);
+    app.add_observer(toggle_beam_focus);
+    app.add_observer(start_dash);
+    app.add_systems(
+        Update,
+        rebuild_player_actions
+            .run_if(resource_changed::<Keybindings>)
+            .run_if(in_state(GameStateMachine::Level)),
+    );
 
     // Gameplay systems
     app.add_systems(
         Update,
         (
-            (tick_player_time, tick_torch_timers),
+            (tick_player_time, tick_torch_timers, tick_stamina),
             (
-                toggle_cursed_controls,
                 enemy_chase_player,
+                apply_dash,
+                detect_mouse_aim,
                 aim_spotlight,
+                animate_beam_focus,
+                flicker_flashlight,
                 update_reflected_spotlight, // mirror bounce (A + C)
                 check_spotlight,
                 on_spotlighted,
@@ -47,7 +74,9 @@ pub(super) fn plugin(app: &mut App) {
                 check_torch,
                 on_torchlit,
                 on_un_torchlit,
+                reveal_in_light,
                 enemy_size,
+                sunrise_burn,
                 enemy_health,
                 player_health,
                 update_vignette,
@@ -68,36 +97,298 @@ pub struct Player;
 #[derive(Component)]
 struct PlayerSpotlight;
 
+/// Spotlights that carry the flashlight's lens color.
+#[derive(Component)]
+struct FlashlightBeam;
+
+#[derive(InputAction)]
+#[action_output(Vec2)]
+pub(super) struct Movement;
+
+/// Directional aim from the right stick or the arrow keys. The mouse aims through the cursor.
 #[derive(InputAction)]
 #[action_output(Vec2)]
-struct Movement;
+pub(super) struct Aim;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct IgniteTorch;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct CycleLens;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ToggleBeamFocus;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct Dash;
+
+/// Held to run faster, for as long as stamina lasts.
+#[derive(InputAction)]
+#[action_output(bool)]
+struct Sprint;
+
+/// Which way the flashlight beam is focused, and how far the switch has animated.
+#[derive(Component, Default)]
+struct BeamFocus {
+    narrow: bool,
+    /// 0 is fully wide, 1 is fully narrow.
+    blend: f32,
+}
+
+/// The player's dash, from the moment it's triggered until it's ready again.
+#[derive(Component)]
+pub(super) struct DashState {
+    /// The way the player last walked. Dashes go this way.
+    facing: Vec3,
+    /// Locked in when the dash starts.
+    direction: Vec3,
+    speed: f32,
+    motion: Timer,
+    invulnerable: Timer,
+    cooldown: Timer,
+}
+
+impl Default for DashState {
+    fn default() -> Self {
+        let finished = |seconds| {
+            let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+            timer.finish();
+            timer
+        };
+        Self {
+            facing: screen_to_world_direction(Vec2::Y),
+            direction: Vec3::ZERO,
+            speed: 0.0,
+            motion: finished(DASH_SECONDS),
+            invulnerable: finished(DASH_INVULNERABLE_SECONDS),
+            cooldown: finished(1.0),
+        }
+    }
+}
+
+impl DashState {
+    fn is_dashing(&self) -> bool {
+        !self.motion.is_finished()
+    }
+
+    fn is_invulnerable(&self) -> bool {
+        !self.invulnerable.is_finished()
+    }
+
+    /// From 0 (just dashed) to 1 (ready to dash).
+    pub fn readiness(&self) -> f32 {
+        self.cooldown.fraction()
+    }
+}
+
+/// How long a dash takes to cover its distance.
+const DASH_SECONDS: f32 = 0.15;
+/// Enemies can't hurt the player for this long after a dash starts. A little longer than the
+/// dash itself so the player isn't punished for landing next to something.
+const DASH_INVULNERABLE_SECONDS: f32 = 0.35;
+
+/// Sprinting multiplies the player's move speed.
+const SPRINT_SPEED_MULTIPLIER: f32 = 1.6;
+/// Stamina (out of 1) spent per second of sprinting, and recovered per second of rest.
+const STAMINA_DRAIN: f32 = 0.3;
+const STAMINA_REGEN: f32 = 0.15;
+/// Once stamina runs dry the player can't sprint again until it's back to this much.
+const STAMINA_RECOVERED: f32 = 0.3;
+/// How long the sound of running feet hangs in the air after the player stops sprinting.
+const SPRINT_NOISE_SECONDS: f32 = 1.5;
+
+/// How far the noise of sprinting carries.
+const SPRINT_HEARING_RANGE: f32 = 30.0;
+/// Enemies that hear the player sprinting rush at them this much faster than usual.
+const SPRINT_HEARD_SPEED_FACTOR: f32 = 1.3;
+
+/// The player's stamina for sprinting, and how loud they've been lately.
+#[derive(Component)]
+pub(super) struct Stamina {
+    /// From 0 (spent) to 1 (rested).
+    pub current: f32,
+    /// Set by `apply_movement` on frames the player sprints.
+    sprinting: bool,
+    /// Ran dry, and hasn't caught their breath yet.
+    exhausted: bool,
+    noise: Timer,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        let mut noise = Timer::from_seconds(SPRINT_NOISE_SECONDS, TimerMode::Once);
+        noise.finish();
+        Self {
+            current: 1.0,
+            sprinting: false,
+            exhausted: false,
+            noise,
+        }
+    }
+}
+
+impl Stamina {
+    fn can_sprint(&self) -> bool {
+        !self.exhausted && self.current > 0.0
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    fn is_noisy(&self) -> bool {
+        !self.noise.is_finished()
+    }
+}
+
+/// A last stand brings the player back with this fraction of their max health, and a moment
+/// of invulnerability to get clear.
+const LAST_STAND_HEALTH: f32 = 0.3;
+const LAST_STAND_INVULNERABLE_SECONDS: f32 = 2.0;
+
+/// How a beam focus scales the flashlight's upgraded stats.
+struct BeamShape {
+    angle: f32,
+    range: f32,
+    intensity: f32,
+    damage: f32,
+}
+
+/// A wide, short, low-damage cone for sweeping crowds.
+const WIDE_BEAM: BeamShape = BeamShape {
+    angle: 1.0,
+    range: 1.0,
+    intensity: 1.0,
+    damage: 1.0,
+};
+
+/// A tight, long-reaching lance that burns whatever it's pointed at.
+const NARROW_BEAM: BeamShape = BeamShape {
+    angle: 0.4,
+    range: 1.8,
+    intensity: 2.5,
+    damage: 2.5,
+};
+
+/// Seconds to switch between the two beam focuses.
+const BEAM_FOCUS_SECONDS: f32 = 0.25;
+
+/// While the batteries are cursed, how often per second the flashlight cuts out, and the
+/// longest it stays dark.
+const FLICKER_CHANCE: f32 = 2.5;
+const FLICKER_MAX_SECONDS: f32 = 0.4;
 
 #[derive(Component)]
-struct Enemy;
+pub(super) struct Enemy;
 
+/// Lit by a spotlight of the given hue.
 #[derive(Component)]
-struct Spotlighted;
+struct Spotlighted(LightHue);
+
+/// Decides which light hues hurt an enemy the most.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub(super) enum EnemyArchetype {
+    Shambler,
+    Stalker,
+    Wraith,
+    Brute,
+    Crawler,
+}
+
+impl EnemyArchetype {
+    pub fn name(self) -> &'static str {
+        match self {
+            EnemyArchetype::Shambler => "Shambler",
+            EnemyArchetype::Stalker => "Stalker",
+            EnemyArchetype::Wraith => "Wraith",
+            EnemyArchetype::Brute => "Brute",
+            EnemyArchetype::Crawler => "Crawler",
+        }
+    }
+
+    fn model(self, assets: &GameAssets) -> Handle<Scene> {
+        match self {
+            EnemyArchetype::Shambler => assets.vox1.clone(),
+            EnemyArchetype::Stalker => assets.vox2.clone(),
+            EnemyArchetype::Wraith => assets.vox3.clone(),
+            EnemyArchetype::Brute => assets.vox4.clone(),
+            EnemyArchetype::Crawler => assets.vox5.clone(),
+        }
+    }
+
+    fn damage_multiplier(self, hue: LightHue) -> f32 {
+        use EnemyArchetype::*;
+        use LightHue::*;
+        match (self, hue) {
+            // Stalkers hunt by sight and recoil from red.
+            (Stalker, Red) => 1.8,
+            (Stalker, White) => 0.6,
+            // Wraiths barely notice ordinary light, but UV and mirrored light burn them.
+            (Wraith, Uv) => 2.5,
+            (Wraith, Mirror) => 1.5,
+            (Wraith, Amber | Red | Ember) => 0.5,
+            // Brutes are thick-skinned except against harsh white light and open flame.
+            (Brute, White) => 1.8,
+            (Brute, Ember) => 1.5,
+            (Brute, Red | Uv) => 0.7,
+            // Crawlers keep low and hate anything bright and warm.
+            (Crawler, Amber) => 1.2,
+            (Crawler, Ember) => 0.8,
+            (Crawler, Mirror) => 1.2,
+            (Shambler, White) => 1.2,
+            (Shambler, Uv) => 0.8,
+            _ => 1.0,
+        }
+    }
+}
 
 #[derive(Component, Reflect)]
 struct SpeedFactor(f32);
 
 #[derive(Component, Reflect)]
-struct Torch {
+pub(super) struct Torch {
     range: f32,
     on_timer: Timer,
     off_timer: Timer,
     is_on: bool,
+    kind: TorchKind,
+}
+
+impl Torch {
+    pub fn is_lit(&self) -> bool {
+        self.is_on
+    }
+}
+
+/// How ready a torch is, from 0 (burnt out) to 1 (ready to ignite or freshly lit).
+/// `slot` is the torch's index in `GameState::torches`, so the HUD can match it up.
+#[derive(Component)]
+pub(super) struct TorchCharge {
+    pub slot: usize,
+    pub charge: f32,
 }
 
 #[derive(Component)]
 struct Torchlit;
 
 #[derive(Component, Reflect)]
-struct Health(f32);
+pub(super) struct Health(pub f32);
 
 #[derive(Component)]
 struct Vox;
 
+/// The model of an enemy, hidden while the enemy is out of sight.
+#[derive(Component)]
+struct EnemyModel;
+
+/// Scenery from the level file, hidden while it's out of sight.
+#[derive(Component)]
+struct Prop;
+
 #[derive(Component)]
 struct EnemySpotlight;
 
@@ -118,14 +409,37 @@ struct Mirror {
 struct ReflectedSpotlight;
 
 #[derive(Component)]
-struct Boss;
+pub(super) struct Boss;
+
+/// An enemy was burned away, at `position`.
+#[derive(Event)]
+pub(super) struct EnemyKilled {
+    pub position: Vec3,
+    pub archetype: EnemyArchetype,
+    pub boss: bool,
+}
 
 /// Mirror collision group for raycasts (so we only hit mirrors)
-const PLAYER_GROUP: Group = Group::GROUP_1;
+pub(super) const PLAYER_GROUP: Group = Group::GROUP_1;
 const MIRROR_GROUP: Group = Group::GROUP_2;
 const ENEMY_GROUP: Group = Group::GROUP_3;
 const WALL_GROUP: Group = Group::GROUP_4;
 const GROUND_GROUP: Group = Group::GROUP_5;
+/// Healing sensors. They only ever need to notice the player.
+pub(super) const PICKUP_GROUP: Group = Group::GROUP_6;
+/// The survivor the player escorts. Kept out of the player's group so it can't use pickups.
+pub(super) const SURVIVOR_GROUP: Group = Group::GROUP_7;
+/// Player-built barricades. Unlike walls, they stop enemies.
+pub(super) const BARRICADE_GROUP: Group = Group::GROUP_8;
+
+/// Damage per second dealt to every non-boss enemy after sunrise.
+const SUNRISE_DAMAGE_PER_SECOND: f32 = 60.0;
+
+/// How close an unlit enemy or prop has to be for the player to make it out in the dark.
+const SIGHT_RADIUS: f32 = 4.0;
+
+/// Side length of the square arena enclosed by the walls.
+pub const ARENA_SIZE: f32 = 50.0;
 
 // ==============================
 // Cursed controls
@@ -166,225 +480,117 @@ impl Default for CursedControls {
     }
 }
 
-#[derive(Resource, Default)]
-pub struct CursedAimState {
-    /// Smoothed/lagged aim direction (horizontal).
-    pub current_dir: Vec3,
+/// Which device the player aimed with most recently.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+enum AimDevice {
+    #[default]
+    Mouse,
+    /// Right stick or arrow keys, through the [`Aim`] action.
+    Directional,
 }
 
-fn toggle_cursed_controls(
-    keys: Res<ButtonInput<KeyCode>>,
-    mut cursed: ResMut<CursedControls>,
-    mut aim_state: ResMut<CursedAimState>,
-    mut rng: Single<&mut WyRand, With<GlobalRng>>,
-) {
-    if !keys.just_pressed(KeyCode::KeyQ) {
-        return;
-    }
-
-    cursed.enabled = !cursed.enabled;
-
-    if cursed.enabled {
-        cursed.speed_mul = rng.random_range(1.4..3.0);
-        cursed.invert = Vec2::new(
-            if rng.random_bool(0.5) { -1.0 } else { 1.0 },
-            if rng.random_bool(0.5) { -1.0 } else { 1.0 },
-        );
-        cursed.skew = Vec2::new(rng.random_range(-1.0..1.0), rng.random_range(-1.0..1.0));
-        cursed.swirl_strength = rng.random_range(0.15..0.75);
+#[derive(Resource, Default)]
+struct AimInput {
+    device: AimDevice,
+    /// Last directional aim input. Kept after the stick is released so the beam stays put.
+    direction: Vec2,
+}
 
-        cursed.aim_rotate_rad = rng.random_range(-std::f32::consts::PI..std::f32::consts::PI);
-        cursed.aim_wobble_rad = rng.random_range(0.15..0.9);
-        cursed.aim_wobble_hz = rng.random_range(0.6..3.5);
-        cursed.aim_lag = rng.random_range(0.04..0.22);
-        cursed.aim_jitter = rng.random_range(0.3..2.5);
+/// How far ahead of the player a directional aim places the aim target.
+const DIRECTIONAL_AIM_DISTANCE: f32 = 5.0;
 
-        aim_state.current_dir = Vec3::ZERO;
-        info!("Cursed controls ENABLED: {:?}", *cursed);
-    } else {
-        info!("Cursed controls disabled.");
-    }
+#[derive(Resource, Default)]
+pub struct CursedAimState {
+    /// Smoothed/lagged aim direction (horizontal).
+    pub current_dir: Vec3,
 }
 
 // ==============================
 // Spawning
 // ==============================
 
+/// The player's input actions, bound to whatever the controls menu has set.
+fn player_actions(keybindings: &Keybindings) -> impl Bundle {
+    let cardinal = |up, down, left, right| Cardinal {
+        north: Binding::from(keybindings.direction_key(up)),
+        east: Binding::from(keybindings.direction_key(right)),
+        south: Binding::from(keybindings.direction_key(down)),
+        west: Binding::from(keybindings.direction_key(left)),
+    };
+    let buttons = |action| Bindings::spawn(SpawnIter(keybindings.bindings(action).into_iter()));
+    actions!(Player[
+        (
+            Action::<Movement>::new(),
+            DeadZone::default(),
+            SmoothNudge::default(),
+            Bindings::spawn((
+                cardinal(
+                    GameAction::MoveUp,
+                    GameAction::MoveDown,
+                    GameAction::MoveLeft,
+                    GameAction::MoveRight,
+                ),
+                Axial::left_stick(),
+            )),
+        ),
+        (
+            Action::<Aim>::new(),
+            DeadZone::default(),
+            Bindings::spawn((
+                Axial::right_stick(),
+                cardinal(
+                    GameAction::AimUp,
+                    GameAction::AimDown,
+                    GameAction::AimLeft,
+                    GameAction::AimRight,
+                ),
+            )),
+        ),
+        (Action::<IgniteTorch>::new(), buttons(GameAction::IgniteTorch)),
+        (Action::<CycleLens>::new(), buttons(GameAction::CycleLens)),
+        (Action::<ToggleBeamFocus>::new(), buttons(GameAction::ToggleBeamFocus)),
+        (Action::<Dash>::new(), buttons(GameAction::Dash)),
+        (Action::<Sprint>::new(), buttons(GameAction::Sprint)),
+    ])
+}
+
+/// Swaps in freshly bound actions when the controls change mid-night (e.g. from the pause menu).
+fn rebuild_player_actions(
+    mut commands: Commands,
+    player: Single<Entity, With<Player>>,
+    keybindings: Res<Keybindings>,
+) {
+    commands
+        .entity(*player)
+        .despawn_related::<Actions<Player>>()
+        .insert(player_actions(&keybindings));
+}
+
 pub fn spawn_level(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
     game_state: Res<GameState>,
+    keybindings: Res<Keybindings>,
 ) {
-    // Ground
-    commands.spawn((
-        DespawnOnExit(GameStateMachine::Level),
-        DespawnOnExit(Screen::Gameplay),
-        Visibility::default(),
-        Mesh3d(meshes.add({
-            let mut mesh = Plane3d::default().mesh().size(1000.0, 1000.0).build();
-            if let Some(VertexAttributeValues::Float32x2(uvs)) =
-                mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0)
-            {
-                for uv in uvs {
-                    uv[0] *= 150.0;
-                    uv[1] *= 150.0;
-                }
-            }
-            mesh
-        })),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color_texture: Some(assets.grass_texture.clone()),
-            reflectance: 0.0,
-            ..default()
-        })),
-        Collider::cuboid(1000.0, 0.1, 1000.0),
-        RigidBody::Fixed,
-        CollisionGroups::new(GROUND_GROUP, Group::ALL),
-    ));
-
-    // Walls
-    let wall_size = 50.0;
-    let wall_height = 2.0;
-    let wall_thickness = 0.1;
-    let wall_color = Color::srgb(0.1, 0.1, 0.1);
-
-    // North
-    commands.spawn((
-        Name::new("Wall North"),
-        DespawnOnExit(GameStateMachine::Level),
-        DespawnOnExit(Screen::Gameplay),
-        Mesh3d(meshes.add(Cuboid::new(wall_size, wall_height, wall_thickness))),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: wall_color,
-            ..default()
-        })),
-        Transform::from_xyz(0.0, wall_height / 2.0, -wall_size / 2.0),
-        RigidBody::Fixed,
-        Collider::cuboid(wall_size / 2.0, wall_height / 2.0, wall_thickness / 2.0),
-        CollisionGroups::new(WALL_GROUP, Group::ALL.difference(ENEMY_GROUP)),
-    ));
-
-    // South
-    commands.spawn((
-        Name::new("Wall South"),
-        DespawnOnExit(GameStateMachine::Level),
-        DespawnOnExit(Screen::Gameplay),
-        Mesh3d(meshes.add(Cuboid::new(wall_size, wall_height, wall_thickness))),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: wall_color,
-            ..default()
-        })),
-        Transform::from_xyz(0.0, wall_height / 2.0, wall_size / 2.0),
-        RigidBody::Fixed,
-        Collider::cuboid(wall_size / 2.0, wall_height / 2.0, wall_thickness / 2.0),
-        CollisionGroups::new(WALL_GROUP, Group::ALL.difference(ENEMY_GROUP)),
-    ));
-
-    // East
-    commands.spawn((
-        Name::new("Wall East"),
-        DespawnOnExit(GameStateMachine::Level),
-        DespawnOnExit(Screen::Gameplay),
-        Mesh3d(meshes.add(Cuboid::new(wall_thickness, wall_height, wall_size))),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: wall_color,
-            ..default()
-        })),
-        Transform::from_xyz(wall_size / 2.0, wall_height / 2.0, 0.0),
-        RigidBody::Fixed,
-        Collider::cuboid(wall_thickness / 2.0, wall_height / 2.0, wall_size / 2.0),
-        CollisionGroups::new(WALL_GROUP, Group::ALL.difference(ENEMY_GROUP)),
-    ));
-
-    // West
-    commands.spawn((
-        Name::new("Wall West"),
-        DespawnOnExit(GameStateMachine::Level),
-        DespawnOnExit(Screen::Gameplay),
-        Mesh3d(meshes.add(Cuboid::new(wall_thickness, wall_height, wall_size))),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: wall_color,
-            ..default()
-        })),
-        Transform::from_xyz(-wall_size / 2.0, wall_height / 2.0, 0.0),
-        RigidBody::Fixed,
-        Collider::cuboid(wall_thickness / 2.0, wall_height / 2.0, wall_size / 2.0),
-        CollisionGroups::new(WALL_GROUP, Group::ALL.difference(ENEMY_GROUP)),
-    ));
-
-    // MIRROR (VERY visible)
-    // Put it close so you cannot miss it.
-    // let mirror_half_extents = Vec3::new(1.5, 2.0, 0.06);
-    // let mirror_size = mirror_half_extents * 2.0;
-
-    // let mirror_mesh = meshes.add(Cuboid::new(mirror_size.x, mirror_size.y, mirror_size.z));
-    // let frame_mesh = meshes.add(Cuboid::new(
-    //     mirror_size.x * 1.06,
-    //     mirror_size.y * 1.06,
-    //     mirror_size.z * 2.0,
-    // ));
-
-    // let mirror_mat = materials.add(StandardMaterial {
-    //     base_color: Color::srgb(0.25, 0.28, 0.35),
-    //     metallic: 1.0,
-    //     perceptual_roughness: 0.12,
-    //     reflectance: 1.0,
-    //     emissive: Color::srgb(0.12, 0.22, 0.55).into(),
-    //     ..default()
-    // });
-
-    // let frame_mat = materials.add(StandardMaterial {
-    //     base_color: Color::srgb(0.02, 0.02, 0.03),
-    //     emissive: Color::srgb(0.25, 0.55, 1.0).into(),
-    //     metallic: 0.0,
-    //     perceptual_roughness: 1.0,
-    //     ..default()
-    // });
-
-    // let mirror_pos = Vec3::new(15., mirror_half_extents.y, -5.0);
-    // let mirror_rot = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
-
-    // commands.spawn((
-    //     Name::new("Mirror"),
-    //     DespawnOnExit(GameStateMachine::Level),DespawnOnExit(Screen::Gameplay),
-    //     Mirror {
-    //         local_normal: Vec3::Z,
-    //     },
-    //     Transform::from_translation(mirror_pos).with_rotation(mirror_rot),
-    //     GlobalTransform::default(),
-    //     Visibility::default(),
-    //     RigidBody::Fixed,
-    //     Collider::cuboid(
-    //         mirror_half_extents.x,
-    //         mirror_half_extents.y,
-    //         mirror_half_extents.z,
-    //     ),
-    //     CollisionGroups::new(MIRROR_GROUP, Group::ALL),
-    //     // visuals + helper light
-    //     children![
-    //         (
-    //             Mesh3d(frame_mesh),
-    //             MeshMaterial3d(frame_mat),
-    //             Transform::default()
-    //         ),
-    //         (
-    //             Mesh3d(mirror_mesh),
-    //             MeshMaterial3d(mirror_mat),
-    //             Transform::default()
-    //         ),
-    //         (
-    //             PointLight {
-    //                 intensity: 2500.0,
-    //                 range: 12.0,
-    //                 color: LIGHT_COLOR,
-    //                 ..default()
-    //             },
-    //             Transform::from_xyz(0.0, 0.0, 0.8)
-    //         )
-    //     ],
-    // ));
+    let level = assets.level(&levels);
+    let layout = game_state.arena_layout(game_state.night_number, level);
+    info!(
+        "Arena seed for night {}: {}",
+        game_state.night_number, layout.seed
+    );
+    spawn_arena(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &assets,
+        level,
+        &layout,
+        GameStateMachine::Level,
+    );
+    commands.insert_resource(layout);
 
     // Reflected spotlight (single entity, toggled visible when player light hits mirror)
     commands.spawn((
@@ -415,75 +621,63 @@ pub fn spawn_level(
         ],
     ));
 
-    // Torch
-    if let Some(torch) = &game_state.torch {
-        commands.spawn((
-            Name::new("Torch"),
-            DespawnOnExit(GameStateMachine::Level),
-            DespawnOnExit(Screen::Gameplay),
-            Visibility::default(),
-            Transform::from_xyz(3.0, 0., 3.0),
-            Torch {
-                range: torch.range,
-                on_timer: Timer::from_seconds(torch.on_seconds, TimerMode::Once),
-                off_timer: Timer::from_seconds(torch.off_seconds, TimerMode::Once),
-                is_on: true,
-            },
-            RigidBody::Fixed,
-            Collider::cuboid(0.5, 0.5, 0.5),
-            children![
-                (
-                    Visibility::default(),
-                    SceneRoot(assets.lamp.clone()),
-                    Transform::from_scale(vec3(0.2, 0.2, 0.2)),
-                ),
-                (
-                    Transform::from_xyz(0.0, 2.5, 0.0),
-                    PointLight {
-                        color: TORCH_COLOR,
-                        intensity: 100000.0,
-                        range: torch.range + 3.,
-                        radius: std::f32::consts::PI,
-                        ..default()
-                    },
-                )
-            ],
-        ));
+    // Torches
+    for (slot, torch) in game_state.torches.iter().enumerate() {
+        let mut on_timer = Timer::from_seconds(torch.on_seconds, TimerMode::Once);
+        let mut off_timer = Timer::from_seconds(torch.off_seconds, TimerMode::Once);
+        // Manual torches start the night dark but fully charged.
+        if torch.kind == TorchKind::Manual {
+            on_timer.finish();
+            off_timer.finish();
+        }
+        commands
+            .spawn((
+                Name::new("Torch"),
+                DespawnOnExit(GameStateMachine::Level),
+                DespawnOnExit(Screen::Gameplay),
+                Torch {
+                    range: torch.range,
+                    on_timer,
+                    off_timer,
+                    is_on: torch.kind == TorchKind::Automatic,
+                    kind: torch.kind,
+                },
+                TorchCharge { slot, charge: 1.0 },
+                RigidBody::Fixed,
+                Collider::cuboid(0.5, 0.5, 0.5),
+                torch_model(&assets, torch),
+            ))
+            .with_child(warm_lamp(&assets));
     }
     // Player
     commands.spawn((
         Name::new("Player"),
         DespawnOnExit(GameStateMachine::Level),
         DespawnOnExit(Screen::Gameplay),
-        SpeedFactor(3.),
-        Health(100.),
+        (
+            Health(game_state.player.max_health),
+            BeamFocus::default(),
+            DashState::default(),
+            Stamina::default(),
+        ),
         Player,
-        actions!(Player[
-            (
-                Action::<Movement>::new(),
-                DeadZone::default(),
-                SmoothNudge::default(),
-                Bindings::spawn((
-                    Cardinal::wasd_keys(),
-                    Axial::left_stick(),
-                )),
-            ),
-        ]),
+        player_actions(&keybindings),
         Visibility::default(),
         RigidBody::KinematicPositionBased,
         Collider::cuboid(0.5, 0.5, 0.5),
         CollisionGroups::new(PLAYER_GROUP, Group::ALL),
-        Transform::from_translation(Vec3::new(0.0, 1.0, 0.0)),
+        Transform::from_translation(level.player_start),
         KinematicCharacterController::default(),
         children![
             (
                 Name::new("Player Spotlight"),
                 DespawnOnExit(GameStateMachine::Level),
                 DespawnOnExit(Screen::Gameplay),
+                FlashlightBeam,
                 PlayerSpotlight,
                 Transform::from_xyz(0.0, -0.8, 0.0),
                 SpotLight {
-                    color: game_state.flashlight.color,
+                    color: game_state.flashlight.lens.color(),
                     outer_angle: game_state.flashlight.angle,
                     inner_angle: game_state.flashlight.angle - 0.1,
                     range: game_state.flashlight.range,
@@ -495,9 +689,10 @@ pub fn spawn_level(
                 Name::new("Player Spotlight2"),
                 DespawnOnExit(GameStateMachine::Level),
                 DespawnOnExit(Screen::Gameplay),
+                FlashlightBeam,
                 Transform::from_xyz(0.0, 2., 0.0),
                 SpotLight {
-                    color: game_state.flashlight.color,
+                    color: game_state.flashlight.lens.color(),
                     outer_angle: game_state.flashlight.angle,
                     inner_angle: game_state.flashlight.angle - 0.1,
                     range: game_state.flashlight.range,
@@ -532,6 +727,277 @@ pub fn spawn_level(
     ));
 }
 
+/// Spawns the ground and everything standing on it. Shared by the level and the between-nights
+/// placement screen, so `state` decides when they get despawned.
+pub(super) fn spawn_arena(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    assets: &GameAssets,
+    level: &LevelFile,
+    layout: &ArenaLayout,
+    state: GameStateMachine,
+) -> Entity {
+    // Ground
+    let ground = commands
+        .spawn((
+            Name::new("Ground"),
+            DespawnOnExit(state),
+            DespawnOnExit(Screen::Gameplay),
+            Visibility::default(),
+            Mesh3d(meshes.add({
+                let mut mesh = Plane3d::default().mesh().size(1000.0, 1000.0).build();
+                if let Some(VertexAttributeValues::Float32x2(uvs)) =
+                    mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0)
+                {
+                    for uv in uvs {
+                        uv[0] *= 150.0;
+                        uv[1] *= 150.0;
+                    }
+                }
+                mesh
+            })),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color_texture: Some(layout.biome.floor_texture(assets)),
+                reflectance: 0.0,
+                ..default()
+            })),
+            Collider::cuboid(1000.0, 0.1, 1000.0),
+            RigidBody::Fixed,
+            CollisionGroups::new(GROUND_GROUP, Group::ALL),
+        ))
+        .id();
+
+    spawn_level_geometry(commands, meshes, materials, level, layout, state);
+
+    ground
+}
+
+/// Spawns the walls, props and mirrors from the level file, and the arena's seeded pieces.
+pub(super) fn spawn_level_geometry(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    level: &LevelFile,
+    layout: &ArenaLayout,
+    state: GameStateMachine,
+) {
+    // Walls
+    let wall_material = materials.add(StandardMaterial {
+        base_color: layout.biome.wall_color(),
+        ..default()
+    });
+    for wall in &level.walls {
+        let size = wall.size;
+        commands.spawn((
+            Name::new(wall.name.clone()),
+            LevelGeometry,
+            DespawnOnExit(state),
+            DespawnOnExit(Screen::Gameplay),
+            Mesh3d(meshes.add(Cuboid::new(size.x, size.y, size.z))),
+            MeshMaterial3d(wall_material.clone()),
+            wall.transform,
+            RigidBody::Fixed,
+            Collider::cuboid(size.x / 2.0, size.y / 2.0, size.z / 2.0),
+            CollisionGroups::new(WALL_GROUP, Group::ALL.difference(ENEMY_GROUP)),
+        ));
+    }
+
+    // Props
+    for prop in &level.props {
+        let mut entity = commands.spawn((
+            Name::new("Prop"),
+            Prop,
+            LevelGeometry,
+            DespawnOnExit(state),
+            DespawnOnExit(Screen::Gameplay),
+            Visibility::default(),
+            SceneRoot(prop.scene.clone()),
+            prop.transform,
+        ));
+        if let Some(size) = prop.collider {
+            entity.insert((
+                RigidBody::Fixed,
+                Collider::cuboid(size.x / 2.0, size.y / 2.0, size.z / 2.0),
+                CollisionGroups::new(WALL_GROUP, Group::ALL.difference(ENEMY_GROUP)),
+            ));
+        }
+    }
+
+    // Mirrors
+    let mirror_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.25, 0.28, 0.35),
+        metallic: 1.0,
+        perceptual_roughness: 0.12,
+        reflectance: 1.0,
+        emissive: Color::srgb(0.12, 0.22, 0.55).into(),
+        ..default()
+    });
+    let frame_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.02, 0.02, 0.03),
+        emissive: Color::srgb(0.25, 0.55, 1.0).into(),
+        metallic: 0.0,
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+    for mirror in &level.mirrors {
+        let size = mirror.size;
+        let half_extents = size / 2.0;
+        commands.spawn((
+            Name::new("Mirror"),
+            LevelGeometry,
+            DespawnOnExit(state),
+            DespawnOnExit(Screen::Gameplay),
+            Mirror {
+                local_normal: Vec3::Z,
+            },
+            mirror.transform,
+            Visibility::default(),
+            RigidBody::Fixed,
+            Collider::cuboid(half_extents.x, half_extents.y, half_extents.z),
+            CollisionGroups::new(MIRROR_GROUP, Group::ALL),
+            children![
+                (
+                    Mesh3d(meshes.add(Cuboid::new(size.x * 1.06, size.y * 1.06, size.z * 2.0))),
+                    MeshMaterial3d(frame_material.clone()),
+                    Transform::default(),
+                ),
+                (
+                    Mesh3d(meshes.add(Cuboid::new(size.x, size.y, size.z))),
+                    MeshMaterial3d(mirror_material.clone()),
+                    Transform::default(),
+                ),
+                (
+                    PointLight {
+                        intensity: 2500.0,
+                        range: 12.0,
+                        color: LIGHT_COLOR,
+                        ..default()
+                    },
+                    Transform::from_xyz(0.0, 0.0, 0.8),
+                ),
+            ],
+        ));
+    }
+
+    // Hazards
+    let dark_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.01, 0.005, 0.02),
+        perceptual_roughness: 1.0,
+        reflectance: 0.0,
+        ..default()
+    });
+    let fog_ground_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.35, 0.38, 0.36),
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+    let fog_material = materials.add(StandardMaterial {
+        base_color: Color::srgba(0.6, 0.65, 0.62, 0.3),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    for hazard in &level.hazards {
+        let (name, ground_material) = match hazard.kind {
+            HazardKind::Dark => ("Dark Zone", dark_material.clone()),
+            HazardKind::Fog => ("Fog", fog_ground_material.clone()),
+        };
+        let mut entity = commands.spawn((
+            Name::new(name),
+            LevelGeometry,
+            DespawnOnExit(state),
+            DespawnOnExit(Screen::Gameplay),
+            Transform::from_translation(hazard.center.with_y(0.0)),
+            Visibility::default(),
+            children![(
+                Mesh3d(meshes.add(Cylinder::new(hazard.radius, 0.02))),
+                MeshMaterial3d(ground_material),
+                Transform::from_xyz(0.0, 0.01, 0.0),
+            )],
+        ));
+        if hazard.kind == HazardKind::Fog {
+            // Stacked sheets of haze hide whatever stands inside.
+            let layer = meshes.add(Cylinder::new(hazard.radius, 0.02));
+            entity.with_children(|parent| {
+                for height in [0.4, 0.9, 1.4, 1.9] {
+                    parent.spawn((
+                        Mesh3d(layer.clone()),
+                        MeshMaterial3d(fog_material.clone()),
+                        Transform::from_xyz(0.0, height, 0.0),
+                    ));
+                }
+            });
+        }
+    }
+
+    // Pillars, ruins and corridors
+    let stone = |color| {
+        materials.add(StandardMaterial {
+            base_color: color,
+            perceptual_roughness: 0.9,
+            ..default()
+        })
+    };
+    let pillar_material = stone(layout.biome.piece_color(PieceKind::Pillar));
+    let ruin_material = stone(layout.biome.piece_color(PieceKind::Ruin));
+    let corridor_material = stone(layout.biome.piece_color(PieceKind::Corridor));
+    for piece in &layout.pieces {
+        let material = match piece.kind {
+            PieceKind::Pillar => pillar_material.clone(),
+            PieceKind::Ruin => ruin_material.clone(),
+            PieceKind::Corridor => corridor_material.clone(),
+        };
+        let size = piece.size;
+        commands
+            .spawn((
+                Name::new(piece.kind.name()),
+                LevelGeometry,
+                DespawnOnExit(state),
+                DespawnOnExit(Screen::Gameplay),
+                Transform::from_translation(piece.position),
+                Visibility::default(),
+                RigidBody::Fixed,
+                Collider::cuboid(size.x / 2.0, size.y / 2.0, size.z / 2.0),
+                CollisionGroups::new(WALL_GROUP, Group::ALL.difference(ENEMY_GROUP)),
+            ))
+            .with_children(|parent| {
+                for (mesh, transform) in layout.biome.piece_shapes(piece.kind, size) {
+                    parent.spawn((
+                        Mesh3d(meshes.add(mesh)),
+                        MeshMaterial3d(material.clone()),
+                        transform,
+                    ));
+                }
+            });
+    }
+}
+
+/// The lamp model and light of a torch, placed at the torch's saved position.
+pub(super) fn torch_model(assets: &GameAssets, torch: &super::Torch) -> impl Bundle {
+    (
+        Visibility::default(),
+        Transform::from_translation(torch.position),
+        children![
+            (
+                Visibility::default(),
+                SceneRoot(assets.lamp.clone()),
+                Transform::from_scale(vec3(0.2, 0.2, 0.2)),
+            ),
+            (
+                Transform::from_xyz(0.0, 2.5, 0.0),
+                PointLight {
+                    color: TORCH_COLOR,
+                    intensity: 100000.0,
+                    range: torch.range + 3.,
+                    radius: std::f32::consts::PI,
+                    ..default()
+                },
+            )
+        ],
+    )
+}
+
 // ==============================
 // Spotlight/torch visual toggles
 // ==============================
@@ -581,6 +1047,48 @@ fn on_un_torchlit(
     }
 }
 
+/// Hides the enemies and props the player can't see. Whatever the flashlight or torchlight
+/// falls on shows, and so does anything close to the player. Once the sun is up, everything does.
+fn reveal_in_light(
+    player: Single<&Transform, With<Player>>,
+    enemies: Query<(&GlobalTransform, &Children, Has<Spotlighted>, Has<Torchlit>), With<Enemy>>,
+    mut models: Query<&mut Visibility, With<EnemyModel>>,
+    mut props: Query<
+        (
+            &GlobalTransform,
+            &mut Visibility,
+            Has<Spotlighted>,
+            Has<Torchlit>,
+        ),
+        (With<Prop>, Without<EnemyModel>),
+    >,
+    game_state: Res<GameState>,
+) {
+    let sunrise = game_state.is_sunrise();
+    let seen = |transform: &GlobalTransform, lit: bool| {
+        sunrise || lit || transform.translation().distance(player.translation) <= SIGHT_RADIUS
+    };
+    let visibility = |seen: bool| {
+        if seen {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    };
+
+    for (transform, children, spotlighted, torchlit) in &enemies {
+        let shown = visibility(seen(transform, spotlighted || torchlit));
+        for &child in children {
+            if let Ok(mut model) = models.get_mut(child) {
+                model.set_if_neq(shown);
+            }
+        }
+    }
+    for (transform, mut prop, spotlighted, torchlit) in &mut props {
+        prop.set_if_neq(visibility(seen(transform, spotlighted || torchlit)));
+    }
+}
+
 fn on_torchlit(
     enemies: Query<&Children, (With<Enemy>, Added<Torchlit>)>,
     mut enemy_spotlights: Query<&mut Visibility, With<EnemyTorchSpotlight>>,
@@ -601,9 +1109,11 @@ fn on_torchlit(
 fn apply_movement(
     movement: On<Fire<Movement>>,
     mut controller: Single<&mut KinematicCharacterController>,
-    player_speed: Single<&SpeedFactor, With<Player>>,
+    mut player: Single<(&mut DashState, &mut Stamina), With<Player>>,
+    sprint: Single<&Action<Sprint>>,
     time: Res<Time>,
     cursed: Res<CursedControls>,
+    game_state: Res<GameState>,
 ) {
     let mut input = movement.value;
 
@@ -617,46 +1127,148 @@ fn apply_movement(
         input = (Vec2::new(x, y) + swirl) * cursed.speed_mul;
     }
 
-    let forward = Vec3::new(-1.0, 0.0, -1.0).normalize();
-    let right = Vec3::new(1.0, 0.0, -1.0).normalize();
-
     // Intentionally not normalized: diagonals & cursed feel “oddly faster”
-    let direction = forward * input.y + right * input.x;
+    let direction = screen_to_world_direction(input);
+
+    let (dash, stamina) = &mut *player;
+    if let Ok(facing) = Dir3::new(direction) {
+        dash.facing = *facing;
+    }
+    // A dash carries the player on its own until it ends.
+    if dash.is_dashing() {
+        return;
+    }
 
-    controller.translation = Some(direction * player_speed.0 * time.delta_secs());
+    // Sprinting scales whatever the curse made of the input, so cursed runs are faster too.
+    let mut speed = game_state.player.move_speed;
+    if **sprint.into_inner() && direction != Vec3::ZERO && stamina.can_sprint() {
+        stamina.sprinting = true;
+        speed *= SPRINT_SPEED_MULTIPLIER;
+    }
+
+    controller.translation = Some(direction * speed * time.delta_secs());
+}
+
+/// Spends stamina on the frames the player sprinted and recovers it on the rest.
+fn tick_stamina(mut stamina: Single<&mut Stamina, With<Player>>, time: Res<Time>) {
+    stamina.noise.tick(time.delta());
+    if stamina.sprinting {
+        stamina.sprinting = false;
+        stamina.noise.reset();
+        stamina.current = (stamina.current - STAMINA_DRAIN * time.delta_secs()).max(0.0);
+        if stamina.current <= 0.0 {
+            stamina.exhausted = true;
+        }
+    } else {
+        stamina.current = (stamina.current + STAMINA_REGEN * time.delta_secs()).min(1.0);
+        if stamina.current >= STAMINA_RECOVERED {
+            stamina.exhausted = false;
+        }
+    }
+}
+
+/// Dashes the way the player is walking. Under cursed controls that's the corrupted direction,
+/// so the curse still steers the dash, but the dash always covers its normal distance.
+fn start_dash(
+    _: On<Start<Dash>>,
+    mut dash: Single<&mut DashState, With<Player>>,
+    game_state: Res<GameState>,
+) {
+    if !dash.cooldown.is_finished() {
+        return;
+    }
+    dash.direction = dash.facing;
+    dash.speed = game_state.dash.distance / DASH_SECONDS;
+    dash.motion.reset();
+    dash.invulnerable = Timer::from_seconds(DASH_INVULNERABLE_SECONDS, TimerMode::Once);
+    dash.cooldown = Timer::from_seconds(game_state.dash.cooldown_seconds, TimerMode::Once);
+}
+
+fn apply_dash(
+    mut player: Single<(&mut DashState, &mut KinematicCharacterController), With<Player>>,
+    time: Res<Time>,
+) {
+    let (ref mut dash, ref mut controller) = *player;
+    dash.cooldown.tick(time.delta());
+    dash.invulnerable.tick(time.delta());
+    if dash.is_dashing() {
+        dash.motion.tick(time.delta());
+        controller.translation = Some(dash.direction * dash.speed * time.delta_secs());
+    }
+}
+
+/// Maps a screen-space input (up is forward) onto the ground plane as seen by the isometric camera.
+fn screen_to_world_direction(input: Vec2) -> Vec3 {
+    let forward = Vec3::new(-1.0, 0.0, -1.0).normalize();
+    let right = Vec3::new(1.0, 0.0, -1.0).normalize();
+    forward * input.y + right * input.x
 }
 
 // ==============================
 // Player aim + cursed aim
 // ==============================
 
+fn record_directional_aim(aim: On<Fire<Aim>>, mut aim_input: ResMut<AimInput>) {
+    aim_input.device = AimDevice::Directional;
+    aim_input.direction = aim.value;
+}
+
+/// Hands aiming back to the mouse as soon as the cursor moves.
+fn detect_mouse_aim(mut cursor_moved: MessageReader<CursorMoved>, mut aim_input: ResMut<AimInput>) {
+    if cursor_moved.read().count() > 0 {
+        aim_input.device = AimDevice::Mouse;
+    }
+}
+
+/// Where the cursor points on the ground plane.
+fn cursor_ground_target(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec3> {
+    let cursor_pos = window.cursor_position()?;
+    let ray = camera
+        .viewport_to_world(camera_transform, cursor_pos)
+        .ok()?;
+
+    let denom = ray.direction.y;
+    if denom.abs() <= 1e-6 {
+        return None;
+    }
+    let t = (0.0 - ray.origin.y) / denom;
+    if t < 0.0 {
+        return None;
+    }
+
+    Some(ray.origin + *ray.direction * t)
+}
+
 fn aim_spotlight(
     window: Single<&Window, With<PrimaryWindow>>,
     camera: Single<(&Camera, &GlobalTransform)>,
     mut player: Single<&mut Transform, With<Player>>,
     time: Res<Time>,
     cursed: Res<CursedControls>,
+    aim_input: Res<AimInput>,
     mut aim_state: ResMut<CursedAimState>,
     mut rng: Single<&mut WyRand, With<GlobalRng>>,
 ) {
-    let Some(cursor_pos) = window.cursor_position() else {
-        return;
+    // Both devices produce a target point, so the cursed corruption below treats them alike.
+    let target = match aim_input.device {
+        AimDevice::Mouse => cursor_ground_target(&window, camera.0, camera.1),
+        AimDevice::Directional => {
+            let direction = screen_to_world_direction(aim_input.direction).normalize_or_zero();
+            if direction == Vec3::ZERO {
+                None
+            } else {
+                Some(player.translation + direction * DIRECTIONAL_AIM_DISTANCE)
+            }
+        }
     };
-    let Ok(ray) = camera.0.viewport_to_world(camera.1, cursor_pos) else {
+    let Some(mut target) = target else {
         return;
     };
 
-    let denom = ray.direction.y;
-    if denom.abs() <= 1e-6 {
-        return;
-    }
-    let t = (0.0 - ray.origin.y) / denom;
-    if t < 0.0 {
-        return;
-    }
-
-    let mut target = ray.origin + *ray.direction * t;
-
     if cursed.enabled {
         let wobble = (time.elapsed_secs() * cursed.aim_wobble_hz * std::f32::consts::TAU).sin()
             * cursed.aim_wobble_rad;
@@ -773,25 +1385,34 @@ fn update_reflected_spotlight(
 
 fn check_torch(
     mut commands: Commands,
-    enemies: Query<(Entity, &GlobalTransform), With<Enemy>>,
+    targets: Query<(Entity, &GlobalTransform), Or<(With<Enemy>, With<Prop>)>>,
     torches: Query<(&GlobalTransform, &Torch)>,
-    mut hit_enemies: Local<HashSet<Entity>>,
+    lamps: Query<(&GlobalTransform, &StreetLamp)>,
+    mut hit_targets: Local<HashSet<Entity>>,
 ) {
-    hit_enemies.clear();
-
-    for (torch_transform, torch) in &torches {
-        let torch_pos = torch_transform.translation();
-
-        for (entity, enemy_transform) in &enemies {
-            let d = torch_pos.distance(enemy_transform.translation());
-            if d <= torch.range && torch.is_on {
-                hit_enemies.insert(entity);
+    hit_targets.clear();
+
+    // Repaired street lamps burn like torches that never go out.
+    let lights =
+        torches
+            .iter()
+            .map(|(transform, torch)| (transform.translation(), torch.range, torch.is_on))
+            .chain(lamps.iter().map(|(transform, lamp)| {
+                (transform.translation(), STREET_LAMP_RANGE, lamp.is_lit())
+            }));
+    for (light_pos, range, is_on) in lights {
+        if !is_on {
+            continue;
+        }
+        for (entity, target_transform) in &targets {
+            if light_pos.distance(target_transform.translation()) <= range {
+                hit_targets.insert(entity);
             }
         }
     }
 
-    for (entity, _) in &enemies {
-        if hit_enemies.contains(&entity) {
+    for (entity, _) in &targets {
+        if hit_targets.contains(&entity) {
             commands.entity(entity).try_insert(Torchlit);
         } else {
             commands.entity(entity).try_remove::<Torchlit>();
@@ -806,35 +1427,53 @@ fn check_torch(
 fn check_spotlight(
     mut commands: Commands,
     rapier_context: ReadRapierContext,
-    enemies: Query<(Entity, &GlobalTransform), With<Enemy>>,
+    enemies: Query<(Entity, &EnemyArchetype), With<Enemy>>,
+    // Props are lit as well, so the darkness can hide them.
+    props: Query<(Entity, &GlobalTransform), With<Prop>>,
     spotlights: Query<
-        (&GlobalTransform, &SpotLight, &Visibility),
+        (
+            Entity,
+            &GlobalTransform,
+            &SpotLight,
+            &Visibility,
+            Has<ReflectedSpotlight>,
+        ),
         Or<(With<PlayerSpotlight>, With<ReflectedSpotlight>)>,
     >,
-    mut hit_enemies: Local<HashSet<Entity>>,
-    mut cached_cone: Local<Option<(f32, f32, Collider)>>, // (range, outer_angle, collider)
+    game_state: Res<GameState>,
+    mut hit_targets: Local<HashMap<Entity, LightHue>>,
+    // Per spotlight, so the beam and its reflection don't keep evicting each other.
+    mut cached_cones: Local<HashMap<Entity, (f32, f32, Collider)>>, // (range, outer_angle, collider)
 ) {
     let rapier_context = rapier_context.single().unwrap();
-    hit_enemies.clear();
+    hit_targets.clear();
+    cached_cones.retain(|entity, _| spotlights.contains(*entity));
 
-    for (spotlight_transform, spotlight, vis) in &spotlights {
+    for (entity, spotlight_transform, spotlight, vis, is_reflected) in &spotlights {
         if matches!(*vis, Visibility::Hidden) {
             continue;
         }
 
+        let hue = if is_reflected {
+            LightHue::Mirror
+        } else {
+            game_state.flashlight.lens
+        };
+
         let range = spotlight.range;
         let outer = spotlight.outer_angle;
 
-        let shape = match cached_cone.as_ref() {
-            Some((r, o, c)) if (*r - range).abs() < 1e-6 && (*o - outer).abs() < 1e-6 => c,
-            _ => {
-                let cone_half_height = range / 2.0;
-                let cone_radius = range * outer.tan();
-                let cone = Collider::cone(cone_half_height, cone_radius);
-                *cached_cone = Some((range, outer, cone));
-                &cached_cone.as_ref().unwrap().2
-            }
-        };
+        // Rebuild whenever the cone changes shape, e.g. while the beam focus animates.
+        let stale = cached_cones
+            .get(&entity)
+            .is_none_or(|(r, o, _)| (*r - range).abs() >= 1e-6 || (*o - outer).abs() >= 1e-6);
+        if stale {
+            let cone_half_height = range / 2.0;
+            let cone_radius = range * outer.tan();
+            let cone = Collider::cone(cone_half_height, cone_radius);
+            cached_cones.insert(entity, (range, outer, cone));
+        }
+        let shape = &cached_cones[&entity].2;
 
         let ray_dir = spotlight_transform.forward().normalize();
         let cone_half_height = range / 2.0;
@@ -850,17 +1489,34 @@ fn check_spotlight(
             shape.raw.as_ref(),
             filter,
             |entity| {
-                if enemies.get(entity).is_ok() {
-                    hit_enemies.insert(entity);
+                if let Ok((_, archetype)) = enemies.get(entity) {
+                    // In the beam and its reflection at once, the hue that hurts the most wins.
+                    let entry = hit_targets.entry(entity).or_insert(hue);
+                    if archetype.damage_multiplier(hue) > archetype.damage_multiplier(*entry) {
+                        *entry = hue;
+                    }
                 }
                 true
             },
         );
+
+        // Not every prop has a collider, so check them against the cone itself.
+        let forward = ray_dir.xz().normalize_or_zero();
+        for (prop, prop_transform) in &props {
+            let offset = (prop_transform.translation() - spotlight_transform.translation()).xz();
+            if offset.length() <= range && offset.normalize_or_zero().dot(forward) >= outer.cos() {
+                hit_targets.entry(prop).or_insert(hue);
+            }
+        }
     }
 
-    for (entity, _) in &enemies {
-        if hit_enemies.contains(&entity) {
-            commands.entity(entity).try_insert(Spotlighted);
+    let targets = enemies
+        .iter()
+        .map(|(entity, _)| entity)
+        .chain(props.iter().map(|(entity, _)| entity));
+    for entity in targets {
+        if let Some(&hue) = hit_targets.get(&entity) {
+            commands.entity(entity).try_insert(Spotlighted(hue));
         } else {
             commands.entity(entity).try_remove::<Spotlighted>();
         }
@@ -872,7 +1528,7 @@ fn check_spotlight(
 // ==============================
 
 fn enemy_chase_player(
-    player: Single<&Transform, (With<Player>, Without<Enemy>)>,
+    player: Single<(&Transform, &Stamina), (With<Player>, Without<Enemy>)>,
     mut enemies: Query<
         (
             &mut Transform,
@@ -882,11 +1538,14 @@ fn enemy_chase_player(
             Has<Spotlighted>,
             Has<Torchlit>,
             Has<Boss>,
+            Option<&LampSmasher>,
+            Option<&Besieging>,
         ),
         With<Enemy>,
     >,
 ) {
-    let player_pos = player.translation;
+    let (player_transform, stamina) = *player;
+    let player_pos = player_transform.translation;
 
     for (
         mut enemy_transform,
@@ -896,18 +1555,37 @@ fn enemy_chase_player(
         is_spotlighted,
         is_torchlit,
         is_boss,
+        smasher,
+        besieging,
     ) in &mut enemies
     {
-        enemy_transform.look_at(player_pos, Vec3::Y);
+        // Lamp smashers on their way to a lamp shrug off the light, like the boss.
+        let lamp = smasher.and_then(|smasher| smasher.target);
+        let goal = match (lamp, besieging) {
+            (Some((_, position)), _) => position,
+            // With a barricade in the way, the barricade has to go first.
+            (None, Some(besieging)) => besieging.position,
+            (None, None) => player_pos,
+        };
+        enemy_transform.look_at(goal.with_y(enemy_transform.translation.y), Vec3::Y);
 
-        if !is_boss && (is_spotlighted || is_torchlit) {
+        if !is_boss && lamp.is_none() && (is_spotlighted || is_torchlit) {
             ext_force.force = Vec3::ZERO;
             continue;
         }
 
-        let direction = (player_pos - enemy_transform.translation) * Vec3::new(1.0, 0.0, 1.0);
+        let direction = (goal - enemy_transform.translation) * Vec3::new(1.0, 0.0, 1.0);
         if direction.length_squared() > 0.01 {
-            let desired_vel = direction.normalize() * speed_factor.0;
+            let heard = lamp.is_none()
+                && stamina.is_noisy()
+                && enemy_transform.translation.xz().distance(player_pos.xz())
+                    <= SPRINT_HEARING_RANGE;
+            let urgency = if heard {
+                SPRINT_HEARD_SPEED_FACTOR
+            } else {
+                1.0
+            };
+            let desired_vel = direction.normalize() * speed_factor.0 * urgency;
             let force_strength = 20.0;
             ext_force.force = (desired_vel - velocity.linvel) * force_strength;
             ext_force.force.y = 0.0;
@@ -945,61 +1623,133 @@ fn enemy_size(
 fn enemy_health(
     mut commands: Commands,
     mut enemies: Query<
-        (Entity, &mut Health, Has<Boss>),
-        (With<Enemy>, Or<(With<Spotlighted>, With<Torchlit>)>),
+        (
+            Entity,
+            &Transform,
+            &mut Health,
+            &EnemyArchetype,
+            Option<&Spotlighted>,
+            Has<Torchlit>,
+            Has<Boss>,
+        ),
+        With<Enemy>,
     >,
+    focus: Single<&BeamFocus>,
     time: Res<Time>,
     game_assets: Res<GameAssets>,
-    mut next_state: ResMut<NextState<GameStateMachine>>,
     mut game_state: ResMut<GameState>,
 ) {
-    for (entity, mut health, is_boss) in enemies.iter_mut() {
-        if is_boss {
-            health.0 -= time.delta_secs() * 5.0;
+    let focus_multiplier = WIDE_BEAM.damage.lerp(NARROW_BEAM.damage, focus.blend);
+
+    for (entity, transform, mut health, archetype, spotlighted, is_torchlit, is_boss) in
+        enemies.iter_mut()
+    {
+        // Standing in several lights doesn't stack, the most effective hue wins.
+        let spotlight_multiplier = spotlighted.map_or(0.0, |hue| {
+            archetype.damage_multiplier(hue.0) * focus_multiplier
+        });
+        let torch_multiplier = if is_torchlit {
+            archetype.damage_multiplier(LightHue::Ember)
         } else {
-            health.0 -= time.delta_secs() * 25.0;
+            0.0
+        };
+        let multiplier = spotlight_multiplier.max(torch_multiplier);
+
+        // Unlit enemies are still checked, they may have burned away at sunrise.
+        if multiplier > 0.0 {
+            if is_boss {
+                health.0 -= time.delta_secs() * 5.0 * multiplier;
+            } else {
+                health.0 -= time.delta_secs() * 25.0 * multiplier;
+            }
         }
         if health.0 <= 0.0 {
             commands.spawn(SamplePlayer::new(game_assets.pop_sound.clone()));
             game_state.kills_this_night += 1;
-            game_state.total_kills += 1;
-            if is_boss {
-                next_state.set(GameStateMachine::End);
-            }
+            commands.trigger(EnemyKilled {
+                position: transform.translation,
+                archetype: *archetype,
+                boss: is_boss,
+            });
             commands.entity(entity).despawn();
         }
     }
 }
 
+/// Once the sun is up, every enemy but the boss burns away.
+fn sunrise_burn(
+    mut enemies: Query<&mut Health, (With<Enemy>, Without<Boss>)>,
+    time: Res<Time>,
+    game_state: Res<GameState>,
+) {
+    if !game_state.is_sunrise() {
+        return;
+    }
+    for mut health in &mut enemies {
+        health.0 -= time.delta_secs() * SUNRISE_DAMAGE_PER_SECOND;
+    }
+}
+
 fn player_health(
-    mut player: Single<(&Transform, &mut Health, &Player)>,
+    mut commands: Commands,
+    mut player: Single<(&Transform, &mut Health, &mut DashState), With<Player>>,
     enemies: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    exposure: Res<HazardExposure>,
     time: Res<Time>,
-    mut game_state: ResMut<NextState<GameStateMachine>>,
+    mut game_state: ResMut<GameState>,
 ) {
+    let was_alive = player.1.0 > 0.0;
+    let stats = &game_state.player;
+    // Slipping through a crowd unharmed is what the dash is for.
+    let invulnerable = player.2.is_invulnerable();
+    let mut threatened = false;
     for enemy_transform in enemies {
         let distance = player.0.translation.distance(enemy_transform.translation);
         if distance < 6.0 {
+            threatened = true;
+        }
+        if distance < 6.0 && !invulnerable {
             let t = 1.0 - (distance / 6.0);
-            let damage_factor = 25.0 * t.powi(2);
+            let damage_factor = 25.0 * t.powi(2) * (1.0 - stats.damage_resistance);
             player.1.0 -= time.delta_secs() * damage_factor;
             player.1.0 = player.1.0.max(0.0);
         }
     }
-    if player.1.0 <= 0.0 {
-        game_state.set(GameStateMachine::Dead);
+    // The darkness gnaws at the player however few enemies are around.
+    if exposure.in_dark {
+        threatened = true;
+        let damage = DARK_ZONE_DAMAGE_PER_SECOND * (1.0 - stats.damage_resistance);
+        player.1.0 = (player.1.0 - damage * time.delta_secs()).max(0.0);
+    }
+    // Wounds only close once the player has broken away from the horde.
+    if !threatened && player.1.0 > 0.0 {
+        player.1.0 =
+            (player.1.0 + stats.regen_per_second * time.delta_secs()).min(stats.max_health);
+    }
+
+    if was_alive && player.1.0 <= 0.0 {
+        if game_state.last_stand {
+            info!("Last stand!");
+            game_state.last_stand = false;
+            player.1.0 = game_state.player.max_health * LAST_STAND_HEALTH;
+            player.2.invulnerable =
+                Timer::from_seconds(LAST_STAND_INVULNERABLE_SECONDS, TimerMode::Once);
+        } else {
+            commands.trigger(NightLost(LossCause::Slain));
+        }
     }
 }
 
-fn update_vignette(player: Single<&Health, With<Player>>, mut camera: Single<&mut CrtSettings>) {
-    let health = (player.0 / 100.0).clamp(0.0, 1.0);
-    // Scale from 0.5 -> 10 as health goes from 100 -> 0 but ramp towards 10 as we get closer to 0 health
+fn update_vignette(
+    player: Single<&Health, With<Player>>,
+    mut camera: Single<&mut CrtSettings>,
+    game_state: Res<GameState>,
+) {
+    let health = (player.0 / game_state.player.max_health).clamp(0.0, 1.0);
+    // Scale from 0.5 -> 10 as health goes from full -> 0 but ramp towards 10 as we get closer to 0 health
     camera.vignette_intensity = 0.5 + 10.0 * (1.0 - health).powi(2);
-    if player.0 <= 0. {
-        camera.brightness = 0.;
-    } else {
-        camera.brightness = 6.0 - 5.0 * (1.0 - health).powi(2);
-    }
+    // At zero health the death sequence takes the picture away.
+    camera.brightness = 6.0 - 5.0 * (1.0 - health).powi(2);
 }
 
 // ==============================
@@ -1027,46 +1777,59 @@ fn enemy_spawner(
     mut commands: Commands,
     mut rng: Single<&mut WyRand, With<GlobalRng>>,
     assets: Res<GameAssets>,
-    enemies: Query<(Entity, Has<Boss>), With<Enemy>>,
+    levels: Res<Assets<LevelFile>>,
+    // Lamp smashers come on their own schedule and don't count towards the horde.
+    enemies: Query<(Entity, Has<Boss>), (With<Enemy>, Without<LampSmasher>)>,
     player_transform: Single<&Transform, With<Player>>,
     game_state: Res<GameState>,
+    objective: Res<ActiveObjective>,
 ) {
     if game_state.night_number == 1 && game_state.kills_this_night == 0 {
         if enemies.is_empty() {
-            spawn_enemy(&mut commands, -10.0, -10.0, assets.vox5.clone(), 3.0, 60.0);
+            spawn_enemy(
+                &mut commands,
+                -10.0,
+                -10.0,
+                assets.vox5.clone(),
+                EnemyArchetype::Crawler,
+                3.0,
+                60.0,
+            );
         }
-    } else if game_state.survived_seconds_this_night >= 150.0 {
-        if enemies.iter().any(|(_, has_boss)| has_boss) {
+    } else if game_state.is_sunrise() {
+        // Only some objectives end the night with a boss. The rest are settled at sunrise.
+        if !objective.brings_boss() || enemies.iter().any(|(_, has_boss)| has_boss) {
             return;
         }
         spawn_boss(&mut commands, &assets);
     } else {
+        let biome = game_state.biome(game_state.night_number);
         let total_enemies = 10 + (game_state.survived_seconds_this_night / 5.0).floor() as usize;
-        let enemies_to_spawn = total_enemies - enemies.count();
+        let enemies_to_spawn = total_enemies.saturating_sub(enemies.count());
 
-        for _ in 0..enemies_to_spawn.max(0) {
+        for _ in 0..enemies_to_spawn {
             let max_health = 10. + (game_state.survived_seconds_this_night / 5.0);
             let health = rng.random_range(5.0..max_health);
-            // Spawn behind the player
-            let back = player_transform.rotation * Vec3::Z;
-            let base_angle = back.z.atan2(back.x);
-            let spread = std::f32::consts::PI;
-            let theta = base_angle + rng.random_range(-spread..spread);
-
-            let radius = rng.random_range(12.0..20.0);
-            let x = player_transform.translation.x + radius * theta.cos();
-            let z = player_transform.translation.z + radius * theta.sin();
-            let speed_factor = rng.random_range(1.0..4.0);
+            let (x, z) = if let Some(point) = assets.level(&levels).spawn_point(&mut **rng) {
+                (point.x, point.z)
+            } else {
+                // Spawn behind the player
+                let back = player_transform.rotation * Vec3::Z;
+                let base_angle = back.z.atan2(back.x);
+                let spread = std::f32::consts::PI;
+                let theta = base_angle + rng.random_range(-spread..spread);
 
-            let vox = match rng.random_range(1..6) {
-                1 => assets.vox1.clone(),
-                2 => assets.vox2.clone(),
-                3 => assets.vox3.clone(),
-                4 => assets.vox4.clone(),
-                5 => assets.vox5.clone(),
-                _ => unreachable!(),
+                let radius = rng.random_range(12.0..20.0);
+                (
+                    player_transform.translation.x + radius * theta.cos(),
+                    player_transform.translation.z + radius * theta.sin(),
+                )
             };
-            spawn_enemy(&mut commands, x, z, vox, speed_factor, health);
+            let speed_factor = rng.random_range(1.0..4.0);
+
+            let archetype = biome.random_enemy(&mut **rng);
+            let vox = archetype.model(&assets);
+            spawn_enemy(&mut commands, x, z, vox, archetype, speed_factor, health);
         }
     }
 }
@@ -1076,39 +1839,229 @@ fn tick_player_time(mut game_state: ResMut<GameState>, time: Res<Time>) {
 }
 
 fn torch_on_off(
-    torch: Single<(&mut Torch, &Children)>,
+    torches: Query<(&Torch, &Children)>,
     mut point_lights: Query<&mut Visibility, With<PointLight>>,
 ) {
-    for child in torch.1 {
-        if let Ok(mut point_light) = point_lights.get_mut(*child) {
-            *point_light = if torch.0.is_on {
-                Visibility::Visible
-            } else {
-                Visibility::Hidden
-            };
+    for (torch, children) in &torches {
+        for child in children {
+            if let Ok(mut point_light) = point_lights.get_mut(*child) {
+                *point_light = if torch.is_on {
+                    Visibility::Visible
+                } else {
+                    Visibility::Hidden
+                };
+            }
         }
     }
 }
 
-fn tick_torch_timers(mut torch: Single<(&mut Torch, &Children)>, time: Res<Time>) {
-    torch.0.is_on = !torch.0.on_timer.is_finished();
+/// Steps `timer` back by `delta`, returning true once it has wound all the way back to zero.
+fn rewind(timer: &mut Timer, delta: std::time::Duration) -> bool {
+    let elapsed = timer.elapsed().saturating_sub(delta);
+    timer.set_elapsed(elapsed);
+    elapsed.is_zero()
+}
 
-    if !torch.0.on_timer.is_finished() {
-        torch.0.on_timer.tick(time.delta());
-        if torch.0.on_timer.just_finished() {
-            torch.0.off_timer.reset();
+fn tick_torch_timers(
+    mut torches: Query<(&mut Torch, &mut TorchCharge)>,
+    curse: Res<Curse>,
+    time: Res<Time>,
+) {
+    if curse.has(CurseEffect::BackwardsTorches) {
+        rewind_torch_timers(torches, time);
+        return;
+    }
+
+    for (mut torch, mut charge) in &mut torches {
+        match torch.kind {
+            TorchKind::Automatic => {
+                torch.is_on = !torch.on_timer.is_finished();
+
+                if !torch.on_timer.is_finished() {
+                    torch.on_timer.tick(time.delta());
+                    if torch.on_timer.just_finished() {
+                        torch.off_timer.reset();
+                    }
+                }
+
+                if !torch.off_timer.is_finished() && torch.on_timer.is_finished() {
+                    torch.off_timer.tick(time.delta());
+
+                    if torch.off_timer.just_finished() {
+                        torch.on_timer.reset();
+                    }
+                }
+
+                charge.charge = if torch.is_on {
+                    torch.on_timer.fraction_remaining()
+                } else {
+                    torch.off_timer.fraction()
+                };
+            }
+            TorchKind::Manual => {
+                // Burn until the on timer runs out, then recharge. Only `ignite_torch` relights it.
+                if torch.is_on {
+                    torch.on_timer.tick(time.delta());
+                    if torch.on_timer.just_finished() {
+                        torch.is_on = false;
+                        torch.off_timer.reset();
+                    }
+                    charge.charge = torch.on_timer.fraction_remaining();
+                } else {
+                    torch.off_timer.tick(time.delta());
+                    charge.charge = torch.off_timer.fraction();
+                }
+            }
         }
     }
+}
 
-    if !torch.0.off_timer.is_finished() && torch.0.on_timer.is_finished() {
-        torch.0.off_timer.tick(time.delta());
+/// Runs the torch cycle in reverse: burning torches un-burn and recharging torches drain.
+fn rewind_torch_timers(mut torches: Query<(&mut Torch, &mut TorchCharge)>, time: Res<Time>) {
+    for (mut torch, mut charge) in &mut torches {
+        let torch = &mut *torch;
+        match torch.kind {
+            TorchKind::Automatic => {
+                if torch.is_on {
+                    // Burning backwards ends where it was lit, which follows a finished dark spell.
+                    if rewind(&mut torch.on_timer, time.delta()) {
+                        torch.on_timer.finish();
+                        torch.off_timer.reset();
+                        torch.off_timer.almost_finish();
+                    }
+                } else if rewind(&mut torch.off_timer, time.delta()) {
+                    torch.off_timer.finish();
+                    torch.on_timer.reset();
+                    torch.on_timer.almost_finish();
+                }
+                torch.is_on = !torch.on_timer.is_finished();
 
-        if torch.0.off_timer.just_finished() {
-            torch.0.on_timer.reset();
+                charge.charge = if torch.is_on {
+                    torch.on_timer.fraction_remaining()
+                } else {
+                    torch.off_timer.fraction()
+                };
+            }
+            TorchKind::Manual => {
+                if torch.is_on {
+                    // Un-burning all the way puts the torch back in the player's hands, unlit.
+                    if rewind(&mut torch.on_timer, time.delta()) {
+                        torch.is_on = false;
+                        torch.off_timer.finish();
+                    }
+                    charge.charge = if torch.is_on {
+                        torch.on_timer.fraction_remaining()
+                    } else {
+                        1.0
+                    };
+                } else {
+                    // A charged torch stays charged, but a recharging one loses its progress.
+                    if !torch.off_timer.is_finished() {
+                        rewind(&mut torch.off_timer, time.delta());
+                    }
+                    charge.charge = torch.off_timer.fraction();
+                }
+            }
         }
     }
 }
 
+/// Cuts the flashlight out at random while a curse drains its batteries.
+fn flicker_flashlight(
+    curse: Res<Curse>,
+    mut beams: Query<&mut Visibility, With<FlashlightBeam>>,
+    mut dark_seconds: Local<f32>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    time: Res<Time>,
+) {
+    if !curse.has(CurseEffect::FlickeringFlashlight) {
+        *dark_seconds = 0.0;
+    } else if *dark_seconds > 0.0 {
+        *dark_seconds -= time.delta_secs();
+    } else if rng.random_bool((FLICKER_CHANCE * time.delta_secs()).min(1.0) as f64) {
+        *dark_seconds = rng.random_range(0.05..FLICKER_MAX_SECONDS);
+    }
+
+    let visibility = if *dark_seconds > 0.0 {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut beam in &mut beams {
+        beam.set_if_neq(visibility);
+    }
+}
+
+/// Lights the closest charged manual torch. Pressing again lights the next one.
+fn ignite_torch(
+    _: On<Start<IgniteTorch>>,
+    player: Single<&Transform, With<Player>>,
+    mut torches: Query<(&mut Torch, &GlobalTransform)>,
+) {
+    let closest = torches
+        .iter_mut()
+        .filter(|(torch, _)| {
+            torch.kind == TorchKind::Manual && !torch.is_on && torch.off_timer.is_finished()
+        })
+        .min_by(|(_, a), (_, b)| {
+            let a = a.translation().distance_squared(player.translation);
+            let b = b.translation().distance_squared(player.translation);
+            a.total_cmp(&b)
+        });
+
+    if let Some((mut torch, _)) = closest {
+        torch.is_on = true;
+        torch.on_timer.reset();
+    }
+}
+
+fn toggle_beam_focus(_: On<Start<ToggleBeamFocus>>, mut focus: Single<&mut BeamFocus>) {
+    focus.narrow = !focus.narrow;
+}
+
+/// Eases the flashlight between the wide and narrow focus, scaling its upgraded stats.
+fn animate_beam_focus(
+    mut focus: Single<&mut BeamFocus>,
+    mut beams: Query<&mut SpotLight, With<FlashlightBeam>>,
+    game_state: Res<GameState>,
+    exposure: Res<HazardExposure>,
+    time: Res<Time>,
+) {
+    let target = if focus.narrow { 1.0 } else { 0.0 };
+    let step = time.delta_secs() / BEAM_FOCUS_SECONDS;
+    let delta = (target - focus.blend).clamp(-step, step);
+    focus.blend += delta;
+
+    let t = focus.blend;
+    let flashlight = &game_state.flashlight;
+    let angle = flashlight.angle * WIDE_BEAM.angle.lerp(NARROW_BEAM.angle, t);
+    for mut beam in &mut beams {
+        beam.outer_angle = angle;
+        beam.inner_angle = (angle - 0.1).max(0.0);
+        beam.range = flashlight.range
+            * WIDE_BEAM.range.lerp(NARROW_BEAM.range, t)
+            * exposure.flashlight_factor();
+        beam.intensity = flashlight.intensity * WIDE_BEAM.intensity.lerp(NARROW_BEAM.intensity, t);
+    }
+}
+
+/// Swaps to the next owned flashlight lens.
+fn cycle_lens(
+    _: On<Start<CycleLens>>,
+    mut game_state: ResMut<GameState>,
+    mut beams: Query<&mut SpotLight, With<FlashlightBeam>>,
+) {
+    let flashlight = &mut game_state.flashlight;
+    let Some(current) = flashlight.lenses.iter().position(|l| *l == flashlight.lens) else {
+        return;
+    };
+    flashlight.lens = flashlight.lenses[(current + 1) % flashlight.lenses.len()];
+
+    for mut beam in &mut beams {
+        beam.color = flashlight.lens.color();
+    }
+}
+
 fn spawn_boss(commands: &mut Commands, assets: &GameAssets) {
     commands
         .spawn((
@@ -1132,6 +2085,7 @@ fn spawn_boss(commands: &mut Commands, assets: &GameAssets) {
             children![
                 (
                     Name::new("Enemy Vox"),
+                    EnemyModel,
                     DespawnOnExit(GameStateMachine::Level),
                     DespawnOnExit(Screen::Gameplay),
                     Visibility::default(),
@@ -1173,19 +2127,21 @@ fn spawn_boss(commands: &mut Commands, assets: &GameAssets) {
         ))
         .insert((
             Boss,
+            EnemyArchetype::Brute,
             DespawnOnExit(Screen::Gameplay),
             DespawnOnExit(GameStateMachine::Level),
         ));
 }
 
-fn spawn_enemy(
+pub(super) fn spawn_enemy(
     commands: &mut Commands,
     x: f32,
     z: f32,
     vox: Handle<Scene>,
+    archetype: EnemyArchetype,
     speed_factor: f32,
     health: f32,
-) {
+) -> Entity {
     let scale = calc_size(health, false);
     commands
         .spawn((
@@ -1209,6 +2165,7 @@ fn spawn_enemy(
             children![
                 (
                     Name::new("Enemy Vox"),
+                    EnemyModel,
                     DespawnOnExit(GameStateMachine::Level),
                     DespawnOnExit(Screen::Gameplay),
                     Visibility::default(),
@@ -1253,7 +2210,9 @@ fn spawn_enemy(
             ],
         ))
         .insert((
+            archetype,
             DespawnOnExit(Screen::Gameplay),
             DespawnOnExit(GameStateMachine::Level),
-        ));
+        ))
+        .id()
 }
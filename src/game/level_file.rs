@@ -0,0 +1,420 @@
+//! Level layouts authored as RON files under `assets/levels`. A layout lists the arena's walls,
+//! props, mirrors, torch slots, player start, enemy spawn zones and hazards, and `spawn_level`
+//! builds the arena from it. With the `file_watcher` dev feature, saving the file rebuilds the
+//! arena in place.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameAssets, GameState, GameStateMachine, level::spawn_level_geometry};
+
+/// The level every night is played in, relative to the assets folder.
+pub const LEVEL_PATH: &str = "levels/arena.level.ron";
+
+/// What every level file starts with. Exporting from the level editor rewrites the rest of the
+/// file, so this is where notes on the format live.
+const FILE_HEADER: &str = "\
+// The arena every night is played in. Positions and sizes are [x, y, z] in metres, with y up,
+// and rotations are degrees around the vertical axis. The playable area is 50 metres across,
+// centred on the origin.
+//
+// Exporting from the dev level editor rewrites this file below this header.
+//
+// generate_pieces: scatter seeded pillars, ruins and corridors over the arena each night.
+// props: e.g. (model: \"vox/Lamp.vox\", position: [8.0, 0.0, 8.0], rotation: 45.0, scale: 0.2,
+//     collider: Some([0.5, 2.0, 0.5])). Props without a collider can be walked through.
+// mirrors: e.g. (position: [15.0, 2.0, -5.0], size: [3.0, 4.0, 0.12], rotation: 90.0)
+// torch_slots: where bought torches are first put, in the order they're bought.
+// street_lamps: broken street lamps the player can repair.
+// spawn_zones: e.g. (center: [20.0, 0.0, 20.0], radius: 4.0). Left empty, enemies creep up
+//     behind the player wherever they are.
+// hazards: dark zones swallow the light and slowly drain the player's health. Fog hides
+//     what's in it.
+";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<LevelFile>();
+    app.init_asset_loader::<LevelFileLoader>();
+    app.add_systems(
+        Update,
+        rebuild_level_geometry
+            .run_if(resource_exists::<GameAssets>)
+            .run_if(in_state(GameStateMachine::Level).or(in_state(GameStateMachine::Placement))),
+    );
+}
+
+/// A loaded level layout.
+#[derive(Asset, TypePath, Debug)]
+pub struct LevelFile {
+    /// Where the player stands when a night starts.
+    pub player_start: Vec3,
+    /// Whether seeded pillars, ruins and corridors are scattered over the layout.
+    pub generate_pieces: bool,
+    pub walls: Vec<LevelWall>,
+    pub props: Vec<LevelProp>,
+    pub mirrors: Vec<LevelMirror>,
+    /// Where newly bought torches are put, in order.
+    pub torch_slots: Vec<Vec3>,
+    /// Where the street lamps stand. They start every night broken, and sit out nights where a
+    /// seeded piece covers them.
+    pub street_lamps: Vec<Vec3>,
+    /// Where enemies come from. With none, they creep up behind the player.
+    pub spawn_zones: Vec<SpawnZone>,
+    /// Patches of thick darkness and fog that are risky to fight in.
+    pub hazards: Vec<LevelHazard>,
+}
+
+/// A solid box, such as one of the arena's outer walls.
+#[derive(Debug)]
+pub struct LevelWall {
+    pub name: String,
+    /// The center of the box.
+    pub transform: Transform,
+    pub size: Vec3,
+}
+
+/// Scenery loaded from a model file.
+#[derive(Clone, Debug)]
+pub struct LevelProp {
+    pub scene: Handle<Scene>,
+    pub transform: Transform,
+    /// The size of the box players bump into. Props without one can be walked through.
+    pub collider: Option<Vec3>,
+}
+
+/// A mirror that bounces the flashlight beam.
+#[derive(Debug)]
+pub struct LevelMirror {
+    pub transform: Transform,
+    pub size: Vec3,
+}
+
+/// A circle on the ground that enemies spawn inside.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnZone {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+/// What a hazard does to the player standing in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HazardKind {
+    /// Swallows the ambient light, shortens the flashlight and slowly drains health.
+    Dark,
+    /// Hides what's inside it and shortens the flashlight.
+    Fog,
+}
+
+/// A circle on the ground covered by a hazard.
+#[derive(Clone, Copy, Debug)]
+pub struct LevelHazard {
+    pub kind: HazardKind,
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl LevelHazard {
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.center.xz().distance(point.xz()) <= self.radius
+    }
+}
+
+impl LevelFile {
+    /// A random point inside a random spawn zone, if the layout has any.
+    pub fn spawn_point(&self, rng: &mut impl Rng) -> Option<Vec3> {
+        if self.spawn_zones.is_empty() {
+            return None;
+        }
+        let zone = self.spawn_zones[rng.random_range(0..self.spawn_zones.len())];
+        let angle = rng.random_range(0.0..std::f32::consts::TAU);
+        // Square root keeps points spread evenly instead of bunching at the center.
+        let distance = zone.radius * rng.random_range(0.0f32..1.0).sqrt();
+        Some(zone.center + Quat::from_rotation_y(angle) * Vec3::X * distance)
+    }
+
+    /// Writes the layout back out in the level file format, under the usual header.
+    pub fn to_ron(&self) -> ron::Result<String> {
+        let source = LevelSource {
+            player_start: self.player_start.to_array(),
+            generate_pieces: self.generate_pieces,
+            walls: self
+                .walls
+                .iter()
+                .map(|wall| WallSource {
+                    name: wall.name.clone(),
+                    position: wall.transform.translation.to_array(),
+                    size: wall.size.to_array(),
+                    rotation: degrees(wall.transform),
+                })
+                .collect(),
+            props: self
+                .props
+                .iter()
+                .filter_map(|prop| {
+                    Some(PropSource {
+                        model: prop.scene.path()?.to_string(),
+                        position: prop.transform.translation.to_array(),
+                        rotation: degrees(prop.transform),
+                        scale: prop.transform.scale.x,
+                        collider: prop.collider.map(Vec3::to_array),
+                    })
+                })
+                .collect(),
+            mirrors: self
+                .mirrors
+                .iter()
+                .map(|mirror| MirrorSource {
+                    position: mirror.transform.translation.to_array(),
+                    size: mirror.size.to_array(),
+                    rotation: degrees(mirror.transform),
+                })
+                .collect(),
+            torch_slots: self
+                .torch_slots
+                .iter()
+                .map(|slot| slot.to_array())
+                .collect(),
+            street_lamps: self
+                .street_lamps
+                .iter()
+                .map(|lamp| lamp.to_array())
+                .collect(),
+            spawn_zones: self
+                .spawn_zones
+                .iter()
+                .map(|zone| SpawnZoneSource {
+                    center: zone.center.to_array(),
+                    radius: zone.radius,
+                })
+                .collect(),
+            hazards: self
+                .hazards
+                .iter()
+                .map(|hazard| HazardSource {
+                    kind: hazard.kind,
+                    center: hazard.center.to_array(),
+                    radius: hazard.radius,
+                })
+                .collect(),
+        };
+        let body = ron::ser::to_string_pretty(&source, ron::ser::PrettyConfig::default())?;
+        Ok(format!("{FILE_HEADER}{body}\n"))
+    }
+}
+
+impl GameAssets {
+    pub fn level<'a>(&self, levels: &'a Assets<LevelFile>) -> &'a LevelFile {
+        levels
+            .get(&self.level)
+            .expect("the level file loads with the game assets")
+    }
+}
+
+/// Marks what a level file spawned, so it can be rebuilt when the file changes.
+#[derive(Component)]
+pub(super) struct LevelGeometry;
+
+/// The layout as written in the file. Positions and sizes are `[x, y, z]` and rotations are
+/// degrees around the vertical axis.
+#[derive(Serialize, Deserialize)]
+struct LevelSource {
+    player_start: [f32; 3],
+    #[serde(default)]
+    generate_pieces: bool,
+    #[serde(default)]
+    walls: Vec<WallSource>,
+    #[serde(default)]
+    props: Vec<PropSource>,
+    #[serde(default)]
+    mirrors: Vec<MirrorSource>,
+    #[serde(default)]
+    torch_slots: Vec<[f32; 3]>,
+    #[serde(default)]
+    street_lamps: Vec<[f32; 3]>,
+    #[serde(default)]
+    spawn_zones: Vec<SpawnZoneSource>,
+    #[serde(default)]
+    hazards: Vec<HazardSource>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WallSource {
+    name: String,
+    position: [f32; 3],
+    size: [f32; 3],
+    #[serde(default)]
+    rotation: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PropSource {
+    /// Path of the model, relative to the assets folder.
+    model: String,
+    position: [f32; 3],
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale: f32,
+    #[serde(default)]
+    collider: Option<[f32; 3]>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MirrorSource {
+    position: [f32; 3],
+    size: [f32; 3],
+    #[serde(default)]
+    rotation: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpawnZoneSource {
+    center: [f32; 3],
+    radius: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HazardSource {
+    kind: HazardKind,
+    center: [f32; 3],
+    radius: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn degrees(transform: Transform) -> f32 {
+    transform.rotation.to_euler(EulerRot::YXZ).0.to_degrees()
+}
+
+fn placed(position: [f32; 3], rotation: f32) -> Transform {
+    Transform::from_translation(Vec3::from_array(position))
+        .with_rotation(Quat::from_rotation_y(rotation.to_radians()))
+}
+
+#[derive(Default, TypePath)]
+struct LevelFileLoader;
+
+impl AssetLoader for LevelFileLoader {
+    type Asset = LevelFile;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<LevelFile, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source: LevelSource = ron::de::from_bytes(&bytes)?;
+
+        Ok(LevelFile {
+            player_start: Vec3::from_array(source.player_start),
+            generate_pieces: source.generate_pieces,
+            walls: source
+                .walls
+                .into_iter()
+                .map(|wall| LevelWall {
+                    name: wall.name,
+                    transform: placed(wall.position, wall.rotation),
+                    size: Vec3::from_array(wall.size),
+                })
+                .collect(),
+            props: source
+                .props
+                .into_iter()
+                .map(|prop| LevelProp {
+                    scene: load_context.load(prop.model),
+                    transform: placed(prop.position, prop.rotation)
+                        .with_scale(Vec3::splat(prop.scale)),
+                    collider: prop.collider.map(Vec3::from_array),
+                })
+                .collect(),
+            mirrors: source
+                .mirrors
+                .into_iter()
+                .map(|mirror| LevelMirror {
+                    transform: placed(mirror.position, mirror.rotation),
+                    size: Vec3::from_array(mirror.size),
+                })
+                .collect(),
+            torch_slots: source
+                .torch_slots
+                .into_iter()
+                .map(Vec3::from_array)
+                .collect(),
+            street_lamps: source
+                .street_lamps
+                .into_iter()
+                .map(Vec3::from_array)
+                .collect(),
+            spawn_zones: source
+                .spawn_zones
+                .into_iter()
+                .map(|zone| SpawnZone {
+                    center: Vec3::from_array(zone.center),
+                    radius: zone.radius,
+                })
+                .collect(),
+            hazards: source
+                .hazards
+                .into_iter()
+                .map(|hazard| LevelHazard {
+                    kind: hazard.kind,
+                    center: Vec3::from_array(hazard.center),
+                    radius: hazard.radius,
+                })
+                .collect(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// Swaps the walls, props, mirrors and seeded pieces for new ones whenever the level file is
+/// saved.
+fn rebuild_level_geometry(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<LevelFile>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    game_state: Res<GameState>,
+    state: Res<State<GameStateMachine>>,
+    geometry: Query<Entity, With<LevelGeometry>>,
+) {
+    if !events.read().any(|event| event.is_modified(&assets.level)) {
+        return;
+    }
+    for entity in &geometry {
+        commands.entity(entity).despawn();
+    }
+    // The seeded pieces depend on the file too, so lay the arena out again. Placement shows the
+    // coming night's arena.
+    let night_number = match state.get() {
+        GameStateMachine::Placement => game_state.night_number + 1,
+        _ => game_state.night_number,
+    };
+    let level = assets.level(&levels);
+    let layout = game_state.arena_layout(night_number, level);
+    spawn_level_geometry(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        level,
+        &layout,
+        *state.get(),
+    );
+    commands.insert_resource(layout);
+    info!("Rebuilt the arena from the changed level file");
+}
@@ -1,9 +1,29 @@
+mod arena;
+mod barricades;
+mod biome;
+mod coins;
+mod curse;
 mod dead;
 mod end;
+mod hazards;
 mod hud;
 mod intro;
+mod lamps;
 mod level;
+mod level_file;
+mod night;
+mod objectives;
+mod pickups;
+mod placement;
 mod shop;
+mod touch;
+
+#[cfg(feature = "dev")]
+pub use curse::toggle_curse;
+#[cfg(feature = "dev")]
+pub use level_file::{
+    HazardKind, LEVEL_PATH, LevelFile, LevelHazard, LevelMirror, LevelWall, SpawnZone,
+};
 
 use bevy::{
     image::{ImageAddressMode, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor},
@@ -11,11 +31,23 @@ use bevy::{
 };
 use bevy_rand::prelude::*;
 use bevy_seedling::sample::AudioSample;
-use rand::seq::SliceRandom;
+use rand::{Rng, seq::SliceRandom};
 
-use crate::{asset_tracking::LoadResource, quotes::QUOTES};
+use crate::{
+    asset_tracking::LoadResource,
+    game::{
+        level::{MIRROR_COLOR, TORCH_COLOR},
+        level_file::LevelFile,
+    },
+    quotes::QUOTES,
+};
 
 pub const LIGHT_COLOR: Color = Color::srgb(1., 195. / 255., 0.0);
+/// Ambient brightness outside of the night, and at dusk when a night begins.
+pub const AMBIENT_BRIGHTNESS: f32 = 35.0;
+pub const WHITE_LENS_COLOR: Color = Color::srgb(1.0, 1.0, 0.95);
+pub const RED_LENS_COLOR: Color = Color::srgb(1.0, 0.1, 0.1);
+pub const UV_LENS_COLOR: Color = Color::srgb(0.45, 0.1, 1.0);
 
 #[derive(States, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub enum GameStateMachine {
@@ -25,6 +57,7 @@ pub enum GameStateMachine {
     Level,
     Dead,
     Shop,
+    Placement,
     End,
 }
 
@@ -33,12 +66,21 @@ pub struct GameState {
     night_number: usize,
     kills_this_night: usize,
     survived_seconds_this_night: f32,
-    total_kills: usize,
+    /// Gold picked up over the run. Whatever hasn't been `spent` can be spent in the shop.
+    gold: usize,
     spent: usize,
     flashlight: Flashlight,
-    torch: Option<Torch>,
+    dash: Dash,
+    player: PlayerStats,
+    /// A purchased last stand, spent the next time the player would die.
+    last_stand: bool,
+    torches: Vec<Torch>,
+    /// Barricades standing in the arena. Ones the enemies break down are gone for good.
+    barricades: Vec<Barricade>,
     quotes: Vec<(String, String)>,
     current_quote_index: usize,
+    /// Seeds every night's arena layout for this run.
+    arena_seed: u64,
 }
 
 #[derive(Resource, Debug, Reflect)]
@@ -46,7 +88,69 @@ pub struct Flashlight {
     angle: f32,
     range: f32,
     intensity: f32,
-    color: Color,
+    /// The lens currently in the flashlight. Always one of `lenses`.
+    lens: LightHue,
+    /// Lenses the player owns and can swap between mid-night.
+    lenses: Vec<LightHue>,
+}
+
+/// The player's dodge, upgraded in the shop.
+#[derive(Resource, Debug, Reflect)]
+pub struct Dash {
+    /// How far one dash carries the player.
+    distance: f32,
+    /// Seconds before the player can dash again.
+    cooldown_seconds: f32,
+}
+
+/// The player's own toughness and speed, upgraded in the shop.
+#[derive(Resource, Debug, Reflect)]
+pub struct PlayerStats {
+    /// Health at the start of each night, and the most regeneration can bring back.
+    max_health: f32,
+    /// Health recovered per second while no enemy is close enough to hurt.
+    regen_per_second: f32,
+    move_speed: f32,
+    /// Fraction of incoming damage shrugged off, from 0 to 1.
+    damage_resistance: f32,
+}
+
+/// The hue of a light. Enemy archetypes take more or less damage depending on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum LightHue {
+    /// The flashlight's stock lens.
+    Amber,
+    White,
+    Red,
+    Uv,
+    /// Torch flames. Not a flashlight lens.
+    Ember,
+    /// Light bounced off a mirror. Not a flashlight lens.
+    Mirror,
+}
+
+impl LightHue {
+    pub fn color(self) -> Color {
+        match self {
+            LightHue::Amber => LIGHT_COLOR,
+            LightHue::White => WHITE_LENS_COLOR,
+            LightHue::Red => RED_LENS_COLOR,
+            LightHue::Uv => UV_LENS_COLOR,
+            LightHue::Ember => TORCH_COLOR,
+            LightHue::Mirror => MIRROR_COLOR,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LightHue::Amber => "Amber",
+            LightHue::White => "White",
+            LightHue::Red => "Red",
+            LightHue::Uv => "UV",
+            LightHue::Ember => "Ember",
+            LightHue::Mirror => "Mirror",
+        }
+    }
 }
 
 #[derive(Resource, Debug, Reflect)]
@@ -54,6 +158,29 @@ pub struct Torch {
     range: f32,
     on_seconds: f32,
     off_seconds: f32,
+    /// Where the torch stands in the arena. Chosen between nights in the placement screen.
+    position: Vec3,
+    kind: TorchKind,
+}
+
+/// A barricade built between nights to hold enemies back.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct Barricade {
+    /// Where the middle of the barricade stands on the ground.
+    position: Vec3,
+    /// Radians around the vertical axis.
+    yaw: f32,
+}
+
+/// How a torch decides when to burn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum TorchKind {
+    /// Cycles between `on_seconds` lit and `off_seconds` dark on its own.
+    #[default]
+    Automatic,
+    /// Stays dark until the player ignites it, burns for `on_seconds`, then recharges for
+    /// `off_seconds`.
+    Manual,
 }
 
 pub(super) fn plugin(app: &mut App) {
@@ -63,31 +190,59 @@ pub(super) fn plugin(app: &mut App) {
     let mut rng = app
         .world_mut()
         .query_filtered::<&mut WyRand, With<GlobalRng>>();
+    let mut arena_seed = 0;
     if let Ok(mut rng) = rng.single_mut(app.world_mut()) {
         quotes.shuffle(&mut rng);
+        arena_seed = rng.random();
     }
 
     app.insert_resource(GameState {
         night_number: 1,
         kills_this_night: 0,
         survived_seconds_this_night: 0.0,
-        total_kills: 0,
+        gold: 0,
         spent: 0,
         flashlight: Flashlight {
             angle: 0.35,
             range: 6.0,
             intensity: 500000.0,
-            color: LIGHT_COLOR,
+            lens: LightHue::Amber,
+            lenses: vec![LightHue::Amber],
         },
-        torch: None,
+        dash: Dash {
+            distance: 4.0,
+            cooldown_seconds: 3.0,
+        },
+        player: PlayerStats {
+            max_health: 100.0,
+            regen_per_second: 0.0,
+            move_speed: 3.0,
+            damage_resistance: 0.0,
+        },
+        last_stand: false,
+        torches: Vec::new(),
+        barricades: Vec::new(),
         quotes,
         current_quote_index: 0,
+        arena_seed,
     });
+    // Registers the level file loader before the game assets ask for a level.
+    app.add_plugins(level_file::plugin);
     app.load_resource::<GameAssets>();
     app.add_plugins(intro::plugin);
     app.add_plugins(shop::plugin);
+    app.add_plugins(placement::plugin);
     app.add_plugins(level::plugin);
+    app.add_plugins(night::plugin);
+    app.add_plugins(curse::plugin);
+    app.add_plugins(pickups::plugin);
+    app.add_plugins(coins::plugin);
+    app.add_plugins(lamps::plugin);
+    app.add_plugins(barricades::plugin);
+    app.add_plugins(hazards::plugin);
+    app.add_plugins(objectives::plugin);
     app.add_plugins(hud::plugin);
+    app.add_plugins(touch::plugin);
     app.add_plugins(dead::plugin);
     app.add_plugins(end::plugin);
 }
@@ -102,6 +257,10 @@ pub struct GameAssets {
     #[dependency]
     grass_texture: Handle<Image>,
     #[dependency]
+    soil_texture: Handle<Image>,
+    #[dependency]
+    moss_texture: Handle<Image>,
+    #[dependency]
     vox0: Handle<Scene>,
     #[dependency]
     vox1: Handle<Scene>,
@@ -117,22 +276,23 @@ pub struct GameAssets {
     lamp: Handle<Scene>,
     #[dependency]
     pop_sound: Handle<AudioSample>,
+    #[dependency]
+    medkit_image: Handle<Image>,
+    #[dependency]
+    warm_heart_image: Handle<Image>,
+    #[dependency]
+    coin_image: Handle<Image>,
+    #[dependency]
+    level: Handle<LevelFile>,
 }
 
 impl FromWorld for GameAssets {
     fn from_world(world: &mut World) -> Self {
         let assets = world.resource::<AssetServer>();
         Self {
-            grass_texture: assets.load_with_settings(
-                "images/textures/planks.png",
-                |settings: &mut ImageLoaderSettings| {
-                    settings.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
-                        address_mode_u: ImageAddressMode::Repeat,
-                        address_mode_v: ImageAddressMode::Repeat,
-                        ..default()
-                    });
-                },
-            ),
+            grass_texture: assets.load_with_settings("images/textures/planks.png", tiled),
+            soil_texture: assets.load_with_settings("images/textures/soil.png", tiled),
+            moss_texture: assets.load_with_settings("images/textures/moss.png", tiled),
             vox0: assets.load("vox/Zeds-0-Zed_1.vox"),
             vox1: assets.load("vox/Zeds-1-Zed_2.vox"),
             vox2: assets.load("vox/Zeds-2-Zed_3.vox"),
@@ -141,6 +301,24 @@ impl FromWorld for GameAssets {
             vox5: assets.load("vox/Zeds-5-Zed_6.vox"),
             lamp: assets.load("vox/Lamp.vox"),
             pop_sound: assets.load("audio/sound_effects/pop.ogg"),
+            medkit_image: assets.load_with_settings("images/medkit.png", pixel_art),
+            warm_heart_image: assets.load_with_settings("images/warm_heart.png", pixel_art),
+            coin_image: assets.load_with_settings("images/coin.png", pixel_art),
+            level: assets.load(level_file::LEVEL_PATH),
         }
     }
 }
+
+/// Repeats floor textures across the whole ground instead of stretching them.
+fn tiled(settings: &mut ImageLoaderSettings) {
+    settings.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..default()
+    });
+}
+
+/// Keeps small sprites crisp instead of blurring them when they're scaled up.
+fn pixel_art(settings: &mut ImageLoaderSettings) {
+    settings.sampler = ImageSampler::nearest();
+}
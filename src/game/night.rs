@@ -0,0 +1,145 @@
+//! Per-night configuration and the time-of-day lighting that plays out over a night.
+
+use bevy::prelude::*;
+
+use crate::{
+    IsometricCamera, PausableSystems,
+    game::{AMBIENT_BRIGHTNESS, GameState, GameStateMachine, LIGHT_COLOR, hazards::HazardExposure},
+};
+
+/// Soft, pale light that washes in as the sun comes up.
+const DAWN_COLOR: Color = Color::srgb(1.0, 0.8, 0.7);
+
+/// How far into the night (0..1) the darkness is deepest.
+const MIDNIGHT: f32 = 0.6;
+
+/// Tuning for a single night.
+#[derive(Clone, Copy, Debug)]
+pub struct NightConfig {
+    /// Seconds from dusk until sunrise.
+    pub duration_seconds: f32,
+    /// Ambient brightness at the darkest point of the night.
+    pub midnight_ambient: f32,
+    /// Ambient brightness once the sun is up.
+    pub dawn_ambient: f32,
+    /// Seconds between curses the night casts on its own, if it casts any.
+    pub curse_interval_seconds: Option<f32>,
+    /// How many effects each curse rolls from the catalog.
+    pub curse_effects: usize,
+    /// Seconds between med kits turning up somewhere in the arena.
+    pub medkit_interval_seconds: f32,
+    /// Chance that a slain enemy leaves a med kit behind.
+    pub medkit_drop_chance: f32,
+}
+
+/// How many nights are tuned individually. Anything per night is listed this many times.
+pub const NIGHT_COUNT: usize = 5;
+
+/// Nights past the end of this list reuse the last entry.
+const NIGHTS: [NightConfig; NIGHT_COUNT] = [
+    NightConfig {
+        duration_seconds: 150.0,
+        midnight_ambient: 15.0,
+        dawn_ambient: 150.0,
+        curse_interval_seconds: None,
+        curse_effects: 1,
+        medkit_interval_seconds: 30.0,
+        medkit_drop_chance: 0.08,
+    },
+    NightConfig {
+        duration_seconds: 150.0,
+        midnight_ambient: 10.0,
+        dawn_ambient: 150.0,
+        curse_interval_seconds: Some(60.0),
+        curse_effects: 1,
+        medkit_interval_seconds: 35.0,
+        medkit_drop_chance: 0.06,
+    },
+    NightConfig {
+        duration_seconds: 150.0,
+        midnight_ambient: 8.0,
+        dawn_ambient: 150.0,
+        curse_interval_seconds: Some(50.0),
+        curse_effects: 2,
+        medkit_interval_seconds: 40.0,
+        medkit_drop_chance: 0.05,
+    },
+    NightConfig {
+        duration_seconds: 150.0,
+        midnight_ambient: 5.0,
+        dawn_ambient: 150.0,
+        curse_interval_seconds: Some(40.0),
+        curse_effects: 2,
+        medkit_interval_seconds: 45.0,
+        medkit_drop_chance: 0.04,
+    },
+    NightConfig {
+        duration_seconds: 150.0,
+        midnight_ambient: 3.0,
+        dawn_ambient: 150.0,
+        curse_interval_seconds: Some(30.0),
+        curse_effects: 3,
+        medkit_interval_seconds: 50.0,
+        medkit_drop_chance: 0.03,
+    },
+];
+
+impl NightConfig {
+    pub fn for_night(night_number: usize) -> Self {
+        NIGHTS[night_index(night_number)]
+    }
+}
+
+/// Where a night's entry sits in per-night tables. Nights past the last share its entry.
+pub fn night_index(night_number: usize) -> usize {
+    night_number.saturating_sub(1).min(NIGHT_COUNT - 1)
+}
+
+impl GameState {
+    pub fn night(&self) -> NightConfig {
+        NightConfig::for_night(self.night_number)
+    }
+
+    /// Whether the sun has come up on the current night.
+    pub fn is_sunrise(&self) -> bool {
+        self.survived_seconds_this_night >= self.night().duration_seconds
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        update_ambient_light
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+    app.add_systems(OnExit(GameStateMachine::Level), reset_ambient_light);
+}
+
+fn update_ambient_light(
+    game_state: Res<GameState>,
+    exposure: Res<HazardExposure>,
+    mut ambient: Single<&mut AmbientLight, With<IsometricCamera>>,
+) {
+    let night = game_state.night();
+    let t = (game_state.survived_seconds_this_night / night.duration_seconds).clamp(0.0, 1.0);
+    let biome_color = game_state.biome(game_state.night_number).ambient_color();
+
+    if t < MIDNIGHT {
+        let f = t / MIDNIGHT;
+        ambient.brightness = AMBIENT_BRIGHTNESS.lerp(night.midnight_ambient, f);
+        ambient.color = biome_color;
+    } else {
+        // Ease in so dawn creeps up slowly and then breaks.
+        let f = ((t - MIDNIGHT) / (1.0 - MIDNIGHT)).powi(3);
+        ambient.brightness = night.midnight_ambient.lerp(night.dawn_ambient, f);
+        ambient.color = biome_color.mix(&DAWN_COLOR, f);
+    }
+    // Thick darkness keeps out even the dawn.
+    ambient.brightness *= exposure.ambient_factor();
+}
+
+fn reset_ambient_light(mut ambient: Single<&mut AmbientLight, With<IsometricCamera>>) {
+    ambient.brightness = AMBIENT_BRIGHTNESS;
+    ambient.color = LIGHT_COLOR;
+}
@@ -0,0 +1,481 @@
+//! What the player has to do to win a night. The first night is always about lasting until
+//! sunrise and slaying the boss, and the run's seed picks the objective of every night after.
+//! The objective decides when the night is won and when it's lost.
+
+use bevy::prelude::*;
+use bevy_rand::prelude::WyRand;
+use bevy_rapier3d::prelude::*;
+use bevy_seedling::sample::SamplePlayer;
+use rand::{Rng, SeedableRng};
+
+use crate::{
+    PausableSystems,
+    game::{
+        GameAssets, GameState, GameStateMachine,
+        arena::ArenaLayout,
+        dead::{LossCause, NightLost},
+        lamps::{StreetLamp, street_lamp},
+        level::{
+            ARENA_SIZE, Enemy, EnemyArchetype, EnemyKilled, Health, Player, SURVIVOR_GROUP,
+            TORCH_COLOR,
+        },
+        level_file::LevelFile,
+        night::night_index,
+    },
+    screens::Screen,
+};
+
+/// Kills needed on the first hunt, and how many more each night after asks for.
+const HUNT_BASE: usize = 12;
+const HUNT_PER_NIGHT: usize = 4;
+
+const BRAZIER_COUNT: usize = 4;
+/// How far from the middle of the arena the braziers stand, if nothing's in the way.
+const BRAZIER_RING_RADIUS: f32 = 16.0;
+/// How close the player has to stand to light a brazier.
+const BRAZIER_LIGHT_RADIUS: f32 = 1.5;
+/// Seconds of standing next to a brazier to light it.
+const BRAZIER_LIGHT_SECONDS: f32 = 2.0;
+const BRAZIER_INTENSITY: f32 = 60000.0;
+
+/// How far from the player's start the protected lamp stands.
+const WARD_LAMP_DISTANCE: f32 = 5.0;
+
+const SURVIVOR_HEALTH: f32 = 100.0;
+/// A touch slower than the player, so the player sets the pace.
+const SURVIVOR_SPEED: f32 = 2.6;
+/// The survivor only follows a player this close.
+const SURVIVOR_FOLLOW_RADIUS: f32 = 8.0;
+/// How close the survivor keeps to the player.
+const SURVIVOR_FOLLOW_DISTANCE: f32 = 1.5;
+/// Damage per second each enemy this close deals to the survivor.
+const SURVIVOR_DAMAGE_RADIUS: f32 = 1.5;
+const SURVIVOR_DAMAGE_PER_SECOND: f32 = 12.0;
+/// How far from the middle of the arena the exit is.
+const EXIT_DISTANCE: f32 = 20.0;
+/// How close to the exit the survivor has to get.
+const EXIT_RADIUS: f32 = 2.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ActiveObjective>();
+    app.add_observer(count_kills);
+    app.add_observer(stop_on_loss);
+    app.add_systems(OnEnter(GameStateMachine::Level), start_objective);
+    app.add_systems(
+        Update,
+        (
+            light_braziers,
+            lead_survivor,
+            hurt_survivor,
+            resolve_objective,
+        )
+            .chain()
+            .run_if(resource_exists::<GameAssets>)
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+}
+
+/// What a night asks of the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum Objective {
+    /// Last until sunrise, then slay the boss that comes with it.
+    SlayBoss,
+    /// Keep a lamp lit until sunrise. Lamp smashers will come for it.
+    ProtectLamp,
+    /// Burn `count` enemies of one archetype before sunrise.
+    Hunt {
+        archetype: EnemyArchetype,
+        count: usize,
+    },
+    /// Light every brazier before sunrise.
+    LightBraziers { count: usize },
+    /// Lead a survivor to the exit before sunrise.
+    Escort,
+}
+
+impl Objective {
+    pub fn description(self) -> String {
+        match self {
+            Objective::SlayBoss => "Last until sunrise, then slay the boss.".to_string(),
+            Objective::ProtectLamp => "Keep the lamp lit until sunrise.".to_string(),
+            Objective::Hunt { archetype, count } => {
+                format!("Burn {count} {}s before sunrise.", archetype.name())
+            }
+            Objective::LightBraziers { count } => {
+                format!("Light all {count} braziers before sunrise.")
+            }
+            Objective::Escort => "Lead the survivor to the exit before sunrise.".to_string(),
+        }
+    }
+
+    /// Whether sunrise brings a boss the player has to slay.
+    pub fn brings_boss(self) -> bool {
+        self == Objective::SlayBoss
+    }
+}
+
+impl GameState {
+    /// The objective of the given night. Like the arena, it's fixed by the run's seed.
+    pub fn objective(&self, night_number: usize) -> Objective {
+        if night_number <= 1 {
+            return Objective::SlayBoss;
+        }
+        let mut rng = WyRand::seed_from_u64(self.arena_seed(night_number).rotate_left(17));
+        match rng.random_range(0..5) {
+            0 => Objective::SlayBoss,
+            1 => Objective::ProtectLamp,
+            2 => Objective::Hunt {
+                archetype: self.biome(night_number).favored_enemy(),
+                count: HUNT_BASE + HUNT_PER_NIGHT * night_index(night_number),
+            },
+            3 => Objective::LightBraziers {
+                count: BRAZIER_COUNT,
+            },
+            _ => Objective::Escort,
+        }
+    }
+}
+
+/// The current night's objective and how far along it is.
+#[derive(Resource)]
+pub(super) struct ActiveObjective {
+    objective: Objective,
+    /// Enemies of the hunted archetype burned so far.
+    hunted: usize,
+    boss_slain: bool,
+    braziers_lit: usize,
+    /// The survivor's health, from 0 to 1.
+    survivor_health: f32,
+    /// How far the survivor still is from the exit.
+    exit_distance: f32,
+    /// The night has been won or lost, and nothing more counts.
+    finished: bool,
+}
+
+impl Default for ActiveObjective {
+    fn default() -> Self {
+        Self {
+            objective: Objective::SlayBoss,
+            hunted: 0,
+            boss_slain: false,
+            braziers_lit: 0,
+            survivor_health: 1.0,
+            exit_distance: f32::INFINITY,
+            finished: false,
+        }
+    }
+}
+
+impl ActiveObjective {
+    pub fn brings_boss(&self) -> bool {
+        self.objective.brings_boss()
+    }
+
+    /// A short line for the HUD.
+    pub fn progress(&self, sunrise: bool) -> String {
+        match self.objective {
+            Objective::SlayBoss if sunrise => "Slay the boss".to_string(),
+            Objective::SlayBoss => "Last until sunrise".to_string(),
+            Objective::ProtectLamp => "Keep the lamp lit until sunrise".to_string(),
+            Objective::Hunt { archetype, count } => {
+                format!("{}s burned: {}/{count}", archetype.name(), self.hunted)
+            }
+            Objective::LightBraziers { count } => {
+                format!("Braziers lit: {}/{count}", self.braziers_lit)
+            }
+            Objective::Escort => format!(
+                "Survivor: {:.0}%  Exit: {:.0}m",
+                self.survivor_health * 100.0,
+                self.exit_distance
+            ),
+        }
+    }
+}
+
+/// The lamp the player has to keep lit.
+#[derive(Component)]
+struct WardLamp;
+
+#[derive(Component)]
+struct Brazier {
+    /// Lighting progress, from 0 (cold) to 1 (lit). Lit braziers stay lit.
+    progress: f32,
+}
+
+impl Brazier {
+    fn is_lit(&self) -> bool {
+        self.progress >= 1.0
+    }
+}
+
+#[derive(Component)]
+struct BrazierFlame;
+
+#[derive(Component)]
+struct Survivor;
+
+#[derive(Component)]
+struct EscortExit;
+
+fn start_objective(
+    mut commands: Commands,
+    mut active: ResMut<ActiveObjective>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    game_state: Res<GameState>,
+) {
+    let objective = game_state.objective(game_state.night_number);
+    *active = ActiveObjective {
+        objective,
+        ..default()
+    };
+    info!("Objective: {}", objective.description());
+
+    let level = assets.level(&levels);
+    let layout = game_state.arena_layout(game_state.night_number, level);
+    let mut rng = WyRand::seed_from_u64(layout.seed);
+    let start = level.player_start.with_y(0.0);
+    match objective {
+        Objective::SlayBoss | Objective::Hunt { .. } => {}
+        Objective::ProtectLamp => {
+            let angle = rng.random_range(0.0..std::f32::consts::TAU);
+            let position = clear_point(&layout, start, angle, WARD_LAMP_DISTANCE);
+            commands.spawn((
+                street_lamp(&assets, position, true),
+                WardLamp,
+                Name::new("Ward Lamp"),
+            ));
+        }
+        Objective::LightBraziers { count } => {
+            let mesh = meshes.add(Cylinder::new(0.4, 0.8));
+            let material = materials.add(StandardMaterial {
+                base_color: Color::srgb(0.15, 0.12, 0.1),
+                metallic: 0.8,
+                perceptual_roughness: 0.6,
+                ..default()
+            });
+            let offset = rng.random_range(0.0..std::f32::consts::TAU);
+            for i in 0..count {
+                let angle = offset + i as f32 / count as f32 * std::f32::consts::TAU;
+                let position = clear_point(&layout, Vec3::ZERO, angle, BRAZIER_RING_RADIUS);
+                commands.spawn((
+                    Name::new("Brazier"),
+                    DespawnOnExit(GameStateMachine::Level),
+                    DespawnOnExit(Screen::Gameplay),
+                    Brazier { progress: 0.0 },
+                    Mesh3d(mesh.clone()),
+                    MeshMaterial3d(material.clone()),
+                    Transform::from_translation(position.with_y(0.4)),
+                    RigidBody::Fixed,
+                    Collider::cylinder(0.4, 0.4),
+                    children![(
+                        BrazierFlame,
+                        Visibility::Hidden,
+                        Transform::from_xyz(0.0, 1.0, 0.0),
+                        PointLight {
+                            color: TORCH_COLOR,
+                            intensity: 0.0,
+                            range: 6.0,
+                            ..default()
+                        },
+                    )],
+                ));
+            }
+        }
+        Objective::Escort => {
+            commands.spawn((
+                Name::new("Survivor"),
+                DespawnOnExit(GameStateMachine::Level),
+                DespawnOnExit(Screen::Gameplay),
+                Survivor,
+                Health(SURVIVOR_HEALTH),
+                Mesh3d(meshes.add(Capsule3d::new(0.3, 0.8))),
+                MeshMaterial3d(materials.add(Color::srgb(0.8, 0.75, 0.65))),
+                Transform::from_translation(start + vec3(1.5, 0.7, 0.0)),
+                RigidBody::Dynamic,
+                Collider::capsule_y(0.4, 0.3),
+                CollisionGroups::new(SURVIVOR_GROUP, Group::ALL),
+                Velocity::default(),
+                LockedAxes::TRANSLATION_LOCKED_Y | LockedAxes::ROTATION_LOCKED,
+            ));
+            let angle = rng.random_range(0.0..std::f32::consts::TAU);
+            let exit = clear_point(&layout, Vec3::ZERO, angle, EXIT_DISTANCE);
+            commands.spawn((
+                Name::new("Exit"),
+                DespawnOnExit(GameStateMachine::Level),
+                DespawnOnExit(Screen::Gameplay),
+                EscortExit,
+                Mesh3d(meshes.add(Cylinder::new(EXIT_RADIUS, 0.05))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: Color::srgba(0.3, 1.0, 0.5, 0.4),
+                    emissive: Color::srgb(0.2, 0.8, 0.4).into(),
+                    alpha_mode: AlphaMode::Blend,
+                    ..default()
+                })),
+                Transform::from_translation(exit.with_y(0.03)),
+                children![(
+                    Transform::from_xyz(0.0, 2.0, 0.0),
+                    PointLight {
+                        color: Color::srgb(0.3, 1.0, 0.5),
+                        intensity: 30000.0,
+                        range: 6.0,
+                        ..default()
+                    },
+                )],
+            ));
+        }
+    }
+}
+
+/// A point `distance` from `center` in the direction of `angle`, pulled in toward the center
+/// until it's clear of the arena's pieces.
+fn clear_point(layout: &ArenaLayout, center: Vec3, angle: f32, distance: f32) -> Vec3 {
+    let limit = ARENA_SIZE / 2.0 - 2.0;
+    let direction = Quat::from_rotation_y(angle) * Vec3::X;
+    let mut distance = distance;
+    loop {
+        let point = (center + direction * distance).clamp(Vec3::splat(-limit), Vec3::splat(limit));
+        if distance <= 1.0 || layout.is_clear(point, 1.0) {
+            return point;
+        }
+        distance -= 1.0;
+    }
+}
+
+fn count_kills(killed: On<EnemyKilled>, mut active: ResMut<ActiveObjective>) {
+    if active.finished {
+        return;
+    }
+    if killed.boss {
+        active.boss_slain = true;
+    }
+    let Objective::Hunt { archetype, .. } = active.objective else {
+        return;
+    };
+    if killed.archetype == archetype {
+        active.hunted += 1;
+    }
+}
+
+fn stop_on_loss(_: On<NightLost>, mut active: ResMut<ActiveObjective>) {
+    active.finished = true;
+}
+
+/// Standing by a cold brazier lights it bit by bit. Walking off lets the embers die down.
+fn light_braziers(
+    mut commands: Commands,
+    mut braziers: Query<(&mut Brazier, &Transform, &Children)>,
+    mut flames: Query<(&mut PointLight, &mut Visibility), With<BrazierFlame>>,
+    player: Single<&Transform, (With<Player>, Without<Brazier>)>,
+    mut active: ResMut<ActiveObjective>,
+    assets: Res<GameAssets>,
+    time: Res<Time>,
+) {
+    let step = time.delta_secs() / BRAZIER_LIGHT_SECONDS;
+    let mut lit = 0;
+    for (mut brazier, transform, children) in &mut braziers {
+        if !brazier.is_lit() {
+            let distance = transform.translation.xz().distance(player.translation.xz());
+            if distance <= BRAZIER_LIGHT_RADIUS {
+                brazier.progress = (brazier.progress + step).min(1.0);
+                if brazier.is_lit() {
+                    commands.spawn(SamplePlayer::new(assets.pop_sound.clone()));
+                }
+            } else {
+                brazier.progress = (brazier.progress - step).max(0.0);
+            }
+        }
+        if brazier.is_lit() {
+            lit += 1;
+        }
+        for &child in children {
+            let Ok((mut light, mut visibility)) = flames.get_mut(child) else {
+                continue;
+            };
+            light.intensity = BRAZIER_INTENSITY * brazier.progress;
+            visibility.set_if_neq(if brazier.progress > 0.0 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+    }
+    active.braziers_lit = lit;
+}
+
+/// The survivor follows a player who stays close, and waits where it is otherwise.
+fn lead_survivor(
+    mut survivor: Single<(&Transform, &mut Velocity), With<Survivor>>,
+    player: Single<&Transform, (With<Player>, Without<Survivor>)>,
+    exit: Single<&Transform, (With<EscortExit>, Without<Survivor>)>,
+    mut active: ResMut<ActiveObjective>,
+) {
+    let (transform, ref mut velocity) = *survivor;
+    let offset = (player.translation - transform.translation).with_y(0.0);
+    let distance = offset.length();
+    velocity.linvel = if distance > SURVIVOR_FOLLOW_DISTANCE && distance < SURVIVOR_FOLLOW_RADIUS {
+        offset.normalize() * SURVIVOR_SPEED
+    } else {
+        Vec3::ZERO
+    };
+    active.exit_distance = transform.translation.xz().distance(exit.translation.xz());
+}
+
+fn hurt_survivor(
+    mut survivor: Single<(&Transform, &mut Health), With<Survivor>>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Survivor>)>,
+    mut active: ResMut<ActiveObjective>,
+    time: Res<Time>,
+) {
+    let (transform, ref mut health) = *survivor;
+    let attackers = enemies
+        .iter()
+        .filter(|enemy| enemy.translation.distance(transform.translation) < SURVIVOR_DAMAGE_RADIUS)
+        .count();
+    health.0 =
+        (health.0 - attackers as f32 * SURVIVOR_DAMAGE_PER_SECOND * time.delta_secs()).max(0.0);
+    active.survivor_health = health.0 / SURVIVOR_HEALTH;
+}
+
+/// Wins or loses the night once the objective says so. Losing goes down like a death.
+fn resolve_objective(
+    mut commands: Commands,
+    mut active: ResMut<ActiveObjective>,
+    ward: Query<&StreetLamp, With<WardLamp>>,
+    game_state: Res<GameState>,
+    mut next_state: ResMut<NextState<GameStateMachine>>,
+) {
+    if active.finished {
+        return;
+    }
+    let sunrise = game_state.is_sunrise();
+    let (won, lost) = match active.objective {
+        Objective::SlayBoss => (active.boss_slain, false),
+        Objective::ProtectLamp => {
+            let lamp_out = ward.iter().any(|lamp| !lamp.is_lit());
+            (sunrise && !lamp_out, lamp_out)
+        }
+        Objective::Hunt { count, .. } => {
+            let done = active.hunted >= count;
+            (done, sunrise && !done)
+        }
+        Objective::LightBraziers { count } => {
+            let done = active.braziers_lit >= count;
+            (done, sunrise && !done)
+        }
+        Objective::Escort => {
+            let done = active.exit_distance <= EXIT_RADIUS;
+            (done, !done && (sunrise || active.survivor_health <= 0.0))
+        }
+    };
+    if won {
+        active.finished = true;
+        next_state.set(GameStateMachine::End);
+    } else if lost {
+        active.finished = true;
+        commands.trigger(NightLost(LossCause::ObjectiveFailed));
+    }
+}
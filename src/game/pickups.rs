@@ -0,0 +1,262 @@
+//! Healing during a night: med kits that turn up around the arena or drop from slain enemies,
+//! and the warm glow around a lit torch. Both heal through Rapier sensors the player walks into.
+
+use bevy::prelude::*;
+use bevy_rand::{global::GlobalRng, prelude::WyRand};
+use bevy_rapier3d::prelude::*;
+use bevy_seedling::sample::SamplePlayer;
+use bevy_sprite3d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    IsometricCamera, PausableSystems,
+    game::{
+        GameAssets, GameState, GameStateMachine,
+        arena::ArenaLayout,
+        level::{ARENA_SIZE, EnemyKilled, Health, PICKUP_GROUP, PLAYER_GROUP, Player, Torch},
+    },
+    screens::Screen,
+};
+
+/// Health restored by a single med kit.
+const MEDKIT_HEAL: f32 = 35.0;
+const MEDKIT_SENSOR_RADIUS: f32 = 0.8;
+/// Random med kits stop turning up once this many are lying around.
+const MAX_MEDKITS: usize = 3;
+/// Random med kits land at least this far from the player, so they're worth a detour.
+const MEDKIT_MIN_DISTANCE: f32 = 8.0;
+/// Keeps random med kits clear of the walls.
+const MEDKIT_WALL_MARGIN: f32 = 3.0;
+
+/// How far from a lit torch its warmth reaches.
+const WARM_LAMP_RADIUS: f32 = 2.5;
+const WARM_LAMP_HEAL_PER_SECOND: f32 = 4.0;
+
+/// Sprite pixels per world unit. The pickup sprites are 16 pixels across.
+const PIXELS_PER_METRE: f32 = 16.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MedkitSpawner>();
+    app.add_observer(drop_medkit);
+    app.add_systems(OnEnter(GameStateMachine::Level), start_medkit_spawner);
+    app.add_systems(
+        Update,
+        (
+            spawn_medkits,
+            collect_medkits,
+            warm_player,
+            show_warm_lamps,
+            face_camera,
+        )
+            .chain()
+            .run_if(resource_exists::<GameAssets>)
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+}
+
+#[derive(Component)]
+struct Medkit;
+
+/// Heals the player while they stand near the parent torch and it's lit.
+#[derive(Component)]
+struct WarmLamp;
+
+/// Sprites that always turn to face the camera.
+#[derive(Component)]
+struct Billboard;
+
+/// Counts down to the next med kit turning up in the arena.
+#[derive(Resource, Default)]
+struct MedkitSpawner(Timer);
+
+fn start_medkit_spawner(mut spawner: ResMut<MedkitSpawner>, game_state: Res<GameState>) {
+    spawner.0 = Timer::from_seconds(
+        game_state.night().medkit_interval_seconds,
+        TimerMode::Repeating,
+    );
+}
+
+fn healing_sensor(radius: f32) -> impl Bundle {
+    (
+        Sensor,
+        Collider::ball(radius),
+        CollisionGroups::new(PICKUP_GROUP, PLAYER_GROUP),
+        // The player is kinematic and pickups are fixed, which Rapier skips by default.
+        ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
+    )
+}
+
+/// A camera-facing sprite. Also used for dropped coins.
+pub(super) fn billboard(image: Handle<Image>) -> impl Bundle {
+    (
+        Billboard,
+        Sprite { image, ..default() },
+        Sprite3d {
+            pixels_per_metre: PIXELS_PER_METRE,
+            alpha_mode: AlphaMode::Mask(0.5),
+            unlit: true,
+            ..default()
+        },
+    )
+}
+
+fn spawn_medkit(commands: &mut Commands, assets: &GameAssets, position: Vec3) {
+    commands.spawn((
+        Name::new("Medkit"),
+        Medkit,
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        Transform::from_translation(position.with_y(0.8)),
+        Visibility::default(),
+        healing_sensor(MEDKIT_SENSOR_RADIUS),
+        children![billboard(assets.medkit_image.clone())],
+    ));
+}
+
+/// The warm glow around a torch. Spawned as a child of each torch in the level.
+pub(super) fn warm_lamp(assets: &GameAssets) -> impl Bundle {
+    (
+        Name::new("Warm Lamp"),
+        WarmLamp,
+        Transform::default(),
+        Visibility::Hidden,
+        healing_sensor(WARM_LAMP_RADIUS),
+        children![(
+            Transform::from_xyz(0.0, 3.5, 0.0).with_scale(Vec3::splat(0.5)),
+            billboard(assets.warm_heart_image.clone()),
+        )],
+    )
+}
+
+fn drop_medkit(
+    killed: On<EnemyKilled>,
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    game_state: Res<GameState>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    if rng.random_bool(game_state.night().medkit_drop_chance as f64) {
+        spawn_medkit(&mut commands, &assets, killed.position);
+    }
+}
+
+fn spawn_medkits(
+    mut commands: Commands,
+    mut spawner: ResMut<MedkitSpawner>,
+    assets: Res<GameAssets>,
+    game_state: Res<GameState>,
+    player: Single<&Transform, With<Player>>,
+    layout: Res<ArenaLayout>,
+    medkits: Query<(), With<Medkit>>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    time: Res<Time>,
+) {
+    if game_state.is_sunrise() || !spawner.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    if medkits.iter().count() >= MAX_MEDKITS {
+        return;
+    }
+
+    let limit = ARENA_SIZE / 2.0 - MEDKIT_WALL_MARGIN;
+    // A few tries is plenty to land outside the player's reach in an arena this size.
+    for _ in 0..8 {
+        let position = vec3(
+            rng.random_range(-limit..limit),
+            0.0,
+            rng.random_range(-limit..limit),
+        );
+        if position.distance(player.translation.with_y(0.0)) >= MEDKIT_MIN_DISTANCE
+            && layout.is_clear(position, MEDKIT_SENSOR_RADIUS)
+        {
+            spawn_medkit(&mut commands, &assets, position);
+            return;
+        }
+    }
+}
+
+fn collect_medkits(
+    mut commands: Commands,
+    rapier_context: ReadRapierContext,
+    mut player: Single<(Entity, &mut Health), With<Player>>,
+    medkits: Query<Entity, With<Medkit>>,
+    assets: Res<GameAssets>,
+    game_state: Res<GameState>,
+) {
+    let rapier_context = rapier_context.single().unwrap();
+    let (player_entity, ref mut health) = *player;
+    // A med kit is too late once the player has fallen.
+    if health.0 <= 0.0 {
+        return;
+    }
+    let max_health = game_state.player.max_health;
+
+    for medkit in &medkits {
+        // Full-health players leave med kits lying for later.
+        if health.0 >= max_health {
+            return;
+        }
+        if rapier_context.intersection_pair(player_entity, medkit) == Some(true) {
+            health.0 = (health.0 + MEDKIT_HEAL).min(max_health);
+            commands.spawn(SamplePlayer::new(assets.pop_sound.clone()));
+            commands.entity(medkit).despawn();
+        }
+    }
+}
+
+fn warm_player(
+    rapier_context: ReadRapierContext,
+    mut player: Single<(Entity, &mut Health), With<Player>>,
+    lamps: Query<(Entity, &ChildOf), With<WarmLamp>>,
+    torches: Query<&Torch>,
+    game_state: Res<GameState>,
+    time: Res<Time>,
+) {
+    let rapier_context = rapier_context.single().unwrap();
+    let (player_entity, ref mut health) = *player;
+    // Warmth can't bring the player back from the brink of death.
+    if health.0 <= 0.0 {
+        return;
+    }
+
+    // Crowding several torches doesn't heal any faster.
+    let warmed = lamps.iter().any(|(lamp, child_of)| {
+        torches.get(child_of.parent()).is_ok_and(Torch::is_lit)
+            && rapier_context.intersection_pair(player_entity, lamp) == Some(true)
+    });
+    if warmed {
+        health.0 = (health.0 + WARM_LAMP_HEAL_PER_SECOND * time.delta_secs())
+            .min(game_state.player.max_health);
+    }
+}
+
+/// Shows the glow's sprite only while its torch burns.
+fn show_warm_lamps(
+    mut lamps: Query<(&mut Visibility, &ChildOf), With<WarmLamp>>,
+    torches: Query<&Torch>,
+) {
+    for (mut visibility, child_of) in &mut lamps {
+        let lit = torches.get(child_of.parent()).is_ok_and(Torch::is_lit);
+        visibility.set_if_neq(if lit {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+fn face_camera(
+    camera: Single<&GlobalTransform, With<IsometricCamera>>,
+    mut billboards: Query<(&mut Transform, &ChildOf), With<Billboard>>,
+    parents: Query<&GlobalTransform>,
+) {
+    let rotation = camera.rotation();
+    for (mut transform, child_of) in &mut billboards {
+        // Undo the parent's turn so the sprite ends up square to the camera in world space.
+        let parent_rotation = parents
+            .get(child_of.parent())
+            .map_or(Quat::IDENTITY, GlobalTransform::rotation);
+        transform.rotation = parent_rotation.inverse() * rotation;
+    }
+}
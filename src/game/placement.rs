@@ -0,0 +1,414 @@
+use bevy::{prelude::*, scene::SceneInstanceReady};
+
+use crate::{
+    IsometricCamera,
+    crt_postprocess::CrtSettings,
+    game::{
+        Barricade, GameAssets, GameState, GameStateMachine,
+        arena::{ArenaLayout, TORCH_CLEARANCE},
+        barricades::{BARRICADE_COST, BARRICADE_SIZE, MAX_BARRICADES, barricade_model},
+        level::{ARENA_SIZE, spawn_arena, torch_model},
+        level_file::LevelFile,
+        shop::SelectedTorch,
+    },
+    screens::Screen,
+    theme::widget,
+};
+
+/// Preview of an owned torch in the placement screen. Holds its index in `GameState::torches`.
+#[derive(Component)]
+struct PlacementTorch(usize);
+
+/// Preview of a built barricade in the placement screen.
+#[derive(Component)]
+struct PlacementBarricade(Barricade);
+
+#[derive(Component)]
+struct PlacementText;
+
+#[derive(Component)]
+struct PlacementHint;
+
+/// What clicking the floor does in the placement screen.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+enum PlacementMode {
+    /// Moves the selected torch.
+    #[default]
+    Torches,
+    /// Builds a barricade turned `yaw` radians around the vertical axis.
+    Barricades { yaw: f32 },
+}
+
+/// How far the camera zooms out so the whole arena fits on screen.
+const PLACEMENT_ZOOM: f32 = 3.0;
+
+/// Keep torches clear of the walls and of the player's spawn point.
+const WALL_MARGIN: f32 = 1.0;
+const SPAWN_CLEARANCE: f32 = 1.5;
+
+/// Barricades keep this far from the player's spawn point, so a night never starts boxed in.
+const BARRICADE_SPAWN_CLEARANCE: f32 = 2.0;
+/// Torches keep this far from a barricade's planks.
+const BARRICADE_TORCH_CLEARANCE: f32 = 1.0;
+/// Barricades can stand close together, but not inside each other.
+const BARRICADE_GAP: f32 = 0.1;
+/// How far one press of the rotate button turns the next barricade.
+const BARRICADE_ROTATION_STEP: f32 = std::f32::consts::FRAC_PI_4;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PlacementMode>();
+    app.add_observer(make_torch_pickable);
+    app.add_systems(
+        OnEnter(GameStateMachine::Placement),
+        (spawn_placement, zoom_out_camera),
+    );
+    app.add_systems(OnExit(GameStateMachine::Placement), reset_camera_zoom);
+    app.add_systems(
+        Update,
+        update_placement_text.run_if(in_state(GameStateMachine::Placement)),
+    );
+}
+
+fn spawn_placement(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    mut game_state: ResMut<GameState>,
+) {
+    let level = assets.level(&levels);
+    // Torches are placed for the coming night, so show the arena that night will have.
+    let layout = game_state.arena_layout(game_state.night_number + 1, level);
+    // Barricades standing where the coming night puts a piece come down, and are paid back.
+    let built = game_state.barricades.len();
+    game_state
+        .barricades
+        .retain(|barricade| barricade.fits(&layout));
+    game_state.spent -= (built - game_state.barricades.len()) * BARRICADE_COST;
+    let ground = spawn_arena(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &assets,
+        level,
+        &layout,
+        GameStateMachine::Placement,
+    );
+    commands.insert_resource(layout);
+    commands.insert_resource(PlacementMode::default());
+    commands
+        .entity(ground)
+        .insert(Pickable::default())
+        .observe(place_selected_torch)
+        .observe(build_barricade);
+
+    for (index, torch) in game_state.torches.iter().enumerate() {
+        commands
+            .spawn((
+                Name::new("Placement Torch"),
+                DespawnOnExit(GameStateMachine::Placement),
+                DespawnOnExit(Screen::Gameplay),
+                PlacementTorch(index),
+                Pickable::default(),
+                torch_model(&assets, torch),
+            ))
+            .observe(select_torch);
+    }
+
+    for barricade in &game_state.barricades {
+        spawn_barricade_preview(&mut commands, &mut meshes, &mut materials, barricade);
+    }
+
+    commands.spawn((
+        GlobalZIndex(1),
+        DespawnOnExit(GameStateMachine::Placement),
+        DespawnOnExit(Screen::Gameplay),
+        Name::new("Placement"),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            height: percent(100),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            padding: UiRect::all(px(20)),
+            ..default()
+        },
+        Pickable::IGNORE,
+        children![
+            (widget::header(""), PlacementText),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: px(10),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                children![
+                    (widget::label(""), PlacementHint),
+                    (
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: px(10),
+                            ..default()
+                        },
+                        Pickable::IGNORE,
+                        children![
+                            widget::button_small("Torches", place_torches),
+                            widget::button_small("Barricades", build_barricades),
+                            widget::button_small("Rotate", rotate_barricade),
+                        ],
+                    ),
+                    widget::button("Done", go_to_shop),
+                ],
+            ),
+        ],
+    ));
+}
+
+fn zoom_out_camera(
+    mut camera: Single<(
+        &mut Transform,
+        &mut Projection,
+        &mut CrtSettings,
+        &IsometricCamera,
+    )>,
+) {
+    let (ref mut transform, ref mut projection, ref mut crt, iso_cam) = *camera;
+    transform.translation = iso_cam.offset;
+    if let Projection::Orthographic(ortho) = &mut **projection {
+        ortho.scale = PLACEMENT_ZOOM;
+    }
+    // The level leaves these wherever the player's health put them.
+    crt.vignette_intensity = 0.5;
+    crt.brightness = 6.0;
+}
+
+fn reset_camera_zoom(mut projection: Single<&mut Projection, With<IsometricCamera>>) {
+    if let Projection::Orthographic(ortho) = &mut **projection {
+        ortho.scale = 1.0;
+    }
+}
+
+fn update_placement_text(
+    game_state: Res<GameState>,
+    selected: Res<SelectedTorch>,
+    mode: Res<PlacementMode>,
+    mut text: Single<&mut Text, With<PlacementText>>,
+    mut hint: Single<&mut Text, (With<PlacementHint>, Without<PlacementText>)>,
+) {
+    match *mode {
+        PlacementMode::Torches => {
+            **text = if game_state.torches.is_empty() {
+                "You don't own any torches".into()
+            } else {
+                format!(
+                    "Placing Torch {}/{}",
+                    selected.0 + 1,
+                    game_state.torches.len()
+                )
+                .into()
+            };
+            **hint =
+                "Click the floor to move the selected torch, or click a torch to select it.".into();
+        }
+        PlacementMode::Barricades { .. } => {
+            **text = format!(
+                "Barricades {}/{}  ({}g each, {}g left)",
+                game_state.barricades.len(),
+                MAX_BARRICADES,
+                BARRICADE_COST,
+                game_state.gold - game_state.spent
+            )
+            .into();
+            **hint = "Click the floor to build a barricade, or click one to take it down for a \
+                      refund."
+                .into();
+        }
+    }
+}
+
+fn place_torches(_: On<Pointer<Click>>, mut mode: ResMut<PlacementMode>) {
+    *mode = PlacementMode::Torches;
+}
+
+fn build_barricades(_: On<Pointer<Click>>, mut mode: ResMut<PlacementMode>) {
+    if *mode == PlacementMode::Torches {
+        *mode = PlacementMode::Barricades { yaw: 0.0 };
+    }
+}
+
+fn rotate_barricade(_: On<Pointer<Click>>, mut mode: ResMut<PlacementMode>) {
+    if let PlacementMode::Barricades { yaw } = &mut *mode {
+        *yaw = (*yaw + BARRICADE_ROTATION_STEP) % std::f32::consts::PI;
+    }
+}
+
+fn spawn_barricade_preview(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    barricade: &Barricade,
+) {
+    commands
+        .spawn((
+            Name::new("Placement Barricade"),
+            DespawnOnExit(GameStateMachine::Placement),
+            DespawnOnExit(Screen::Gameplay),
+            PlacementBarricade(*barricade),
+            Pickable::default(),
+            barricade_model(meshes, materials, barricade),
+        ))
+        .observe(take_down_barricade);
+}
+
+fn build_barricade(
+    click: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut game_state: ResMut<GameState>,
+    mode: Res<PlacementMode>,
+    layout: Res<ArenaLayout>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+) {
+    let PlacementMode::Barricades { yaw } = *mode else {
+        return;
+    };
+    let Some(hit) = click.hit.position else {
+        return;
+    };
+    if game_state.barricades.len() >= MAX_BARRICADES
+        || game_state.gold - game_state.spent < BARRICADE_COST
+    {
+        return;
+    }
+
+    let limit = ARENA_SIZE / 2.0 - WALL_MARGIN - BARRICADE_SIZE.x / 2.0;
+    let position = vec3(hit.x.clamp(-limit, limit), 0.0, hit.z.clamp(-limit, limit));
+    let barricade = Barricade { position, yaw };
+    let start = assets.level(&levels).player_start.with_y(0.0);
+    let crowded = barricade.distance_to(start) < BARRICADE_SPAWN_CLEARANCE
+        || game_state
+            .torches
+            .iter()
+            .any(|torch| barricade.distance_to(torch.position) < BARRICADE_TORCH_CLEARANCE)
+        || game_state
+            .barricades
+            .iter()
+            .any(|other| barricade.distance_to_barricade(other) < BARRICADE_GAP)
+        || !barricade.fits(&layout);
+    if crowded {
+        return;
+    }
+
+    game_state.barricades.push(barricade);
+    game_state.spent += BARRICADE_COST;
+    spawn_barricade_preview(&mut commands, &mut meshes, &mut materials, &barricade);
+}
+
+fn take_down_barricade(
+    click: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    mode: Res<PlacementMode>,
+    previews: Query<&PlacementBarricade>,
+) {
+    if *mode == PlacementMode::Torches {
+        return;
+    }
+    let Ok(preview) = previews.get(click.event_target()) else {
+        return;
+    };
+    game_state
+        .barricades
+        .retain(|barricade| *barricade != preview.0);
+    game_state.spent -= BARRICADE_COST;
+    commands.entity(click.event_target()).despawn();
+}
+
+/// Mesh picking only hits meshes marked `Pickable`, so mark the torch model's meshes once its
+/// scene spawns. Clicks on them bubble up to the `PlacementTorch`.
+fn make_torch_pickable(
+    ready: On<SceneInstanceReady>,
+    mut commands: Commands,
+    parents: Query<&ChildOf>,
+    children: Query<&Children>,
+    torches: Query<(), With<PlacementTorch>>,
+    meshes: Query<(), With<Mesh3d>>,
+) {
+    let in_torch = parents
+        .iter_ancestors(ready.entity)
+        .any(|ancestor| torches.contains(ancestor));
+    if !in_torch {
+        return;
+    }
+    for descendant in children.iter_descendants(ready.entity) {
+        if meshes.contains(descendant) {
+            commands.entity(descendant).insert(Pickable::default());
+        }
+    }
+}
+
+fn select_torch(
+    click: On<Pointer<Click>>,
+    torches: Query<&PlacementTorch>,
+    mut selected: ResMut<SelectedTorch>,
+) {
+    if let Ok(torch) = torches.get(click.event_target()) {
+        selected.0 = torch.0;
+    }
+}
+
+fn place_selected_torch(
+    click: On<Pointer<Click>>,
+    mut game_state: ResMut<GameState>,
+    selected: Res<SelectedTorch>,
+    mode: Res<PlacementMode>,
+    layout: Res<ArenaLayout>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+    mut previews: Query<(&PlacementTorch, &mut Transform)>,
+) {
+    if *mode != PlacementMode::Torches {
+        return;
+    }
+    let Some(hit) = click.hit.position else {
+        return;
+    };
+    if selected.0 >= game_state.torches.len() {
+        return;
+    }
+
+    let limit = ARENA_SIZE / 2.0 - WALL_MARGIN;
+    let mut position = vec3(hit.x.clamp(-limit, limit), 0.0, hit.z.clamp(-limit, limit));
+    let start = assets.level(&levels).player_start.with_y(0.0);
+    let from_start = position - start;
+    if from_start.length() < SPAWN_CLEARANCE {
+        position = start + from_start.normalize_or(Vec3::X) * SPAWN_CLEARANCE;
+    }
+    let on_barricade = game_state
+        .barricades
+        .iter()
+        .any(|barricade| barricade.distance_to(position) < BARRICADE_TORCH_CLEARANCE);
+    if on_barricade || !layout.is_clear(position, TORCH_CLEARANCE) {
+        return;
+    }
+    let Some(torch) = game_state.torches.get_mut(selected.0) else {
+        return;
+    };
+    torch.position = position;
+
+    for (preview, mut transform) in &mut previews {
+        if preview.0 == selected.0 {
+            transform.translation = position;
+        }
+    }
+}
+
+fn go_to_shop(_: On<Pointer<Click>>, mut state: ResMut<NextState<GameStateMachine>>) {
+    state.set(GameStateMachine::Shop);
+}
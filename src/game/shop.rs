@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 
 use crate::{
-    game::{GameState, GameStateMachine, Torch},
+    game::{
+        GameAssets, GameState, GameStateMachine, LightHue, Torch, TorchKind, level_file::LevelFile,
+    },
     screens::Screen,
     theme::widget,
 };
@@ -18,12 +20,63 @@ struct FlashlightRangeText;
 #[derive(Component)]
 struct FlashlightRangeButton;
 
+#[derive(Component)]
+struct LensText;
+
+#[derive(Component)]
+struct BuyLensButton(LightHue);
+
+const LENS_COST: usize = 60;
+
+#[derive(Component)]
+struct DashDistanceText;
+
+#[derive(Component)]
+struct DashDistanceButton;
+
+#[derive(Component)]
+struct DashCooldownText;
+
+#[derive(Component)]
+struct DashCooldownButton;
+
+/// A stat of the player's own that the shop sells upgrades for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PlayerUpgrade {
+    MaxHealth,
+    Regeneration,
+    MoveSpeed,
+    Resistance,
+}
+
+#[derive(Component)]
+struct PlayerUpgradeText(PlayerUpgrade);
+
+#[derive(Component)]
+struct PlayerUpgradeButton(PlayerUpgrade);
+
+const PLAYER_UPGRADE_COST: usize = 45;
+
+#[derive(Component)]
+struct LastStandText;
+
+#[derive(Component)]
+struct BuyLastStandButton;
+
+const LAST_STAND_COST: usize = 150;
+
 #[derive(Component)]
 struct BuyTorchUI;
 
 #[derive(Component)]
 struct UpgradeTorchUI;
 
+#[derive(Component)]
+struct SelectedTorchText;
+
+#[derive(Component)]
+struct TorchKindText;
+
 #[derive(Component)]
 struct TorchRangeText;
 
@@ -45,11 +98,27 @@ struct TorchOffSecondsButton;
 #[derive(Component)]
 struct CurrencyText;
 
+/// The most torches the player can own at once.
+const MAX_TORCHES: usize = 4;
+
+/// Which of the owned torches the shop upgrades and the placement screen moves.
+#[derive(Resource, Default)]
+pub(super) struct SelectedTorch(pub usize);
+
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SelectedTorch>();
     app.add_systems(OnEnter(GameStateMachine::Shop), spawn_shop);
     app.add_systems(
         Update,
-        (update_torch_ui, update_flashlight_ui, update_currency)
+        (
+            update_torch_ui,
+            update_flashlight_ui,
+            update_lens_ui,
+            update_dash_ui,
+            update_player_ui,
+            update_last_stand_ui,
+            update_currency,
+        )
             .run_if(in_state(GameStateMachine::Shop)),
     );
 }
@@ -147,6 +216,81 @@ fn upgrades() -> impl Bundle {
                     ),
                 ]
             ),
+            (widget::label(""), LensText),
+            (
+                Name::new("Lenses"),
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(10),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                Visibility::default(),
+                children![
+                    buy_lens_button(LightHue::White),
+                    buy_lens_button(LightHue::Red),
+                    buy_lens_button(LightHue::Uv),
+                ]
+            ),
+            widget::label(""),
+            widget::header("Dash"),
+            (
+                Name::new("Dash Distance"),
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(10),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                Visibility::default(),
+                children![
+                    (widget::label(""), DashDistanceText,),
+                    (
+                        widget::button_small("+", increase_dash_distance),
+                        DashDistanceButton,
+                    ),
+                ]
+            ),
+            (
+                Name::new("Dash Cooldown"),
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(10),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                Visibility::default(),
+                children![
+                    (widget::label(""), DashCooldownText,),
+                    (
+                        widget::button_small("+", decrease_dash_cooldown),
+                        DashCooldownButton,
+                    ),
+                ]
+            ),
+            widget::label(""),
+            widget::header("Player"),
+            player_upgrade_row(PlayerUpgrade::MaxHealth),
+            player_upgrade_row(PlayerUpgrade::Regeneration),
+            player_upgrade_row(PlayerUpgrade::MoveSpeed),
+            player_upgrade_row(PlayerUpgrade::Resistance),
+            (
+                Name::new("Last Stand"),
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(10),
+                    ..default()
+                },
+                Pickable::IGNORE,
+                Visibility::default(),
+                children![
+                    (widget::label(""), LastStandText),
+                    (
+                        widget::button_small("+", buy_last_stand),
+                        BuyLastStandButton,
+                    ),
+                ]
+            ),
             widget::label(""),
             widget::header("Torch"),
             (
@@ -162,6 +306,35 @@ fn upgrades() -> impl Bundle {
                     ..default()
                 },
                 children![
+                    (
+                        Name::new("Selected Torch"),
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: px(10),
+                            ..default()
+                        },
+                        Pickable::IGNORE,
+                        Visibility::default(),
+                        children![
+                            widget::button_small("<", select_previous_torch),
+                            (widget::label(""), SelectedTorchText,),
+                            widget::button_small(">", select_next_torch),
+                        ]
+                    ),
+                    (
+                        Name::new("Kind"),
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: px(10),
+                            ..default()
+                        },
+                        Pickable::IGNORE,
+                        Visibility::default(),
+                        children![
+                            (widget::label(""), TorchKindText,),
+                            widget::button_small("~", toggle_torch_kind),
+                        ]
+                    ),
                     (
                         Name::new("Range"),
                         Node {
@@ -212,7 +385,8 @@ fn upgrades() -> impl Bundle {
                                 TorchOffSecondsButton,
                             ),
                         ]
-                    )
+                    ),
+                    widget::button("Place Torches", go_to_placement),
                 ],
             )
         ],
@@ -234,7 +408,7 @@ fn update_currency(
     game_state: Res<GameState>,
     mut currency_text: Single<&mut Text, With<CurrencyText>>,
 ) {
-    **currency_text = format!("Currency: {}g", game_state.total_kills - game_state.spent).into();
+    **currency_text = format!("Currency: {}g", game_state.gold - game_state.spent).into();
 }
 
 fn update_flashlight_ui(
@@ -265,16 +439,115 @@ fn update_flashlight_ui(
     }
 }
 
+fn update_lens_ui(
+    game_state: Res<GameState>,
+    mut lens_text: Single<&mut Text, With<LensText>>,
+    mut buttons: Query<(&BuyLensButton, &mut Visibility)>,
+) {
+    **lens_text = format!(
+        "Lens: {} (R to swap) - ({LENS_COST}g each)",
+        game_state.flashlight.lens.name()
+    )
+    .into();
+
+    for (button, mut visibility) in &mut buttons {
+        *visibility = if game_state.flashlight.lenses.contains(&button.0) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+fn update_dash_ui(
+    game_state: Res<GameState>,
+    mut dash_distance: Single<&mut Text, With<DashDistanceText>>,
+    mut dash_cooldown: Single<&mut Text, (With<DashCooldownText>, Without<DashDistanceText>)>,
+    mut increase_dash_distance_button: Single<&mut Visibility, With<DashDistanceButton>>,
+    mut decrease_dash_cooldown_button: Single<
+        &mut Visibility,
+        (With<DashCooldownButton>, Without<DashDistanceButton>),
+    >,
+) {
+    **dash_distance = format!("Distance: {:.0} - (45g)", game_state.dash.distance).into();
+    **dash_cooldown = format!("Cooldown: {:.2}s - (45g)", game_state.dash.cooldown_seconds).into();
+
+    if max_dash_distance(&game_state) {
+        **increase_dash_distance_button = Visibility::Hidden;
+    }
+    if min_dash_cooldown(&game_state) {
+        **decrease_dash_cooldown_button = Visibility::Hidden;
+    }
+}
+
+fn update_player_ui(
+    game_state: Res<GameState>,
+    mut texts: Query<(&mut Text, &PlayerUpgradeText)>,
+    mut buttons: Query<(&mut Visibility, &PlayerUpgradeButton)>,
+) {
+    for (mut text, upgrade) in &mut texts {
+        **text = format!(
+            "{} - ({PLAYER_UPGRADE_COST}g)",
+            upgrade.0.label(&game_state)
+        )
+        .into();
+    }
+    for (mut visibility, upgrade) in &mut buttons {
+        *visibility = if upgrade.0.is_maxed(&game_state) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+fn update_last_stand_ui(
+    game_state: Res<GameState>,
+    mut text: Single<&mut Text, With<LastStandText>>,
+    mut button: Single<&mut Visibility, With<BuyLastStandButton>>,
+) {
+    **text = if game_state.last_stand {
+        "Last Stand: Ready".into()
+    } else {
+        format!("Last Stand: Revive once - ({LAST_STAND_COST}g)").into()
+    };
+    **button = if game_state.last_stand {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+}
+
 fn update_torch_ui(
     game_state: Res<GameState>,
-    mut torch_range: Single<&mut Text, With<TorchRangeText>>,
-    mut torch_on_seconds: Single<&mut Text, (With<TorchOnSecondsText>, Without<TorchRangeText>)>,
+    mut selected: ResMut<SelectedTorch>,
+    mut selected_torch: Single<&mut Text, With<SelectedTorchText>>,
+    mut torch_kind: Single<&mut Text, (With<TorchKindText>, Without<SelectedTorchText>)>,
+    mut torch_range: Single<
+        &mut Text,
+        (
+            With<TorchRangeText>,
+            Without<SelectedTorchText>,
+            Without<TorchKindText>,
+        ),
+    >,
+    mut torch_on_seconds: Single<
+        &mut Text,
+        (
+            With<TorchOnSecondsText>,
+            Without<TorchRangeText>,
+            Without<SelectedTorchText>,
+            Without<TorchKindText>,
+        ),
+    >,
     mut torch_off_seconds: Single<
         &mut Text,
         (
             With<TorchOffSecondsText>,
             Without<TorchRangeText>,
             Without<TorchOnSecondsText>,
+            Without<SelectedTorchText>,
+            Without<TorchKindText>,
         ),
     >,
     mut buy_torch_ui: Single<&mut Visibility, With<BuyTorchUI>>,
@@ -297,31 +570,84 @@ fn update_torch_ui(
         ),
     >,
 ) {
-    match &game_state.torch {
-        None => {
-            **buy_torch_ui = Visibility::Visible;
-            **upgrade_torch_ui = Visibility::Hidden;
-        }
-        Some(torch) => {
-            **buy_torch_ui = Visibility::Hidden;
-            **upgrade_torch_ui = Visibility::Visible;
-            **torch_range = format!("Range: {:.0} - (45g)", torch.range).into();
-            **torch_on_seconds = format!("Duration: {:.1} - (45g)", torch.on_seconds).into();
-            **torch_off_seconds =
-                format!("Cooldown Reduction: {:.1} - (45g)", torch.off_seconds).into();
-
-            if max_torch_range(&game_state) {
-                **increase_torch_range_button = Visibility::Hidden;
-            }
-            if max_torch_off_seconds(&game_state) {
-                **decrease_torch_off_seconds_button = Visibility::Hidden;
-            }
-        }
+    **buy_torch_ui = if game_state.torches.len() < MAX_TORCHES {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+
+    if game_state.torches.is_empty() {
+        **upgrade_torch_ui = Visibility::Hidden;
+        return;
+    }
+
+    selected.0 = selected.0.min(game_state.torches.len() - 1);
+    let torch = &game_state.torches[selected.0];
+
+    **upgrade_torch_ui = Visibility::Visible;
+    **selected_torch = format!("Torch {}/{}", selected.0 + 1, game_state.torches.len()).into();
+    **torch_kind = match torch.kind {
+        TorchKind::Automatic => "Type: Automatic",
+        TorchKind::Manual => "Type: Manual (E to ignite)",
     }
+    .into();
+    **torch_range = format!("Range: {:.0} - (45g)", torch.range).into();
+    **torch_on_seconds = format!("Duration: {:.1} - (45g)", torch.on_seconds).into();
+    **torch_off_seconds = match torch.kind {
+        TorchKind::Automatic => format!("Cooldown Reduction: {:.1} - (45g)", torch.off_seconds),
+        TorchKind::Manual => format!("Recharge Reduction: {:.1} - (45g)", torch.off_seconds),
+    }
+    .into();
+
+    **increase_torch_range_button = if max_torch_range(torch) {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    **decrease_torch_off_seconds_button = if max_torch_off_seconds(torch) {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+}
+
+fn select_previous_torch(
+    _: On<Pointer<Click>>,
+    game_state: Res<GameState>,
+    mut selected: ResMut<SelectedTorch>,
+) {
+    let count = game_state.torches.len().max(1);
+    selected.0 = (selected.0 + count - 1) % count;
+}
+
+fn select_next_torch(
+    _: On<Pointer<Click>>,
+    game_state: Res<GameState>,
+    mut selected: ResMut<SelectedTorch>,
+) {
+    selected.0 = (selected.0 + 1) % game_state.torches.len().max(1);
+}
+
+fn toggle_torch_kind(
+    _: On<Pointer<Click>>,
+    mut game_state: ResMut<GameState>,
+    selected: Res<SelectedTorch>,
+) {
+    let Some(torch) = game_state.torches.get_mut(selected.0) else {
+        return;
+    };
+    torch.kind = match torch.kind {
+        TorchKind::Automatic => TorchKind::Manual,
+        TorchKind::Manual => TorchKind::Automatic,
+    };
+}
+
+fn go_to_placement(_: On<Pointer<Click>>, mut state: ResMut<NextState<GameStateMachine>>) {
+    state.set(GameStateMachine::Placement);
 }
 
 fn can_buy_falshlight_angle(game_state: &GameState) -> bool {
-    !max_flashlight_angle(game_state) && game_state.total_kills - game_state.spent >= 45
+    !max_flashlight_angle(game_state) && game_state.gold - game_state.spent >= 45
 }
 
 fn max_flashlight_angle(game_state: &GameState) -> bool {
@@ -337,7 +663,7 @@ fn increase_fashlight_angle(_: On<Pointer<Click>>, mut game_state: ResMut<GameSt
 }
 
 fn can_buy_flashlight_range(game_state: &GameState) -> bool {
-    !max_flashlight_range(game_state) && game_state.total_kills - game_state.spent >= 45
+    !max_flashlight_range(game_state) && game_state.gold - game_state.spent >= 45
 }
 
 fn max_flashlight_range(game_state: &GameState) -> bool {
@@ -352,69 +678,243 @@ fn increase_fashlight_range(_: On<Pointer<Click>>, mut game_state: ResMut<GameSt
     game_state.spent += 45;
 }
 
+fn buy_lens_button(hue: LightHue) -> impl Bundle {
+    (
+        BuyLensButton(hue),
+        widget::button(
+            format!("{} Lens", hue.name()),
+            move |_: On<Pointer<Click>>, mut game_state: ResMut<GameState>| {
+                if !can_buy_lens(&game_state, hue) {
+                    return;
+                }
+                game_state.flashlight.lenses.push(hue);
+                game_state.spent += LENS_COST;
+            },
+        ),
+    )
+}
+
+fn can_buy_lens(game_state: &GameState, hue: LightHue) -> bool {
+    !game_state.flashlight.lenses.contains(&hue) && game_state.gold - game_state.spent >= LENS_COST
+}
+
+fn max_dash_distance(game_state: &GameState) -> bool {
+    game_state.dash.distance >= 8.0
+}
+
+fn can_buy_dash_distance(game_state: &GameState) -> bool {
+    !max_dash_distance(game_state) && game_state.gold - game_state.spent >= 45
+}
+
+fn increase_dash_distance(_: On<Pointer<Click>>, mut game_state: ResMut<GameState>) {
+    if !can_buy_dash_distance(&game_state) {
+        return;
+    }
+    game_state.dash.distance += 1.0;
+    game_state.spent += 45;
+}
+
+fn min_dash_cooldown(game_state: &GameState) -> bool {
+    game_state.dash.cooldown_seconds <= 1.0
+}
+
+fn can_buy_dash_cooldown(game_state: &GameState) -> bool {
+    !min_dash_cooldown(game_state) && game_state.gold - game_state.spent >= 45
+}
+
+fn decrease_dash_cooldown(_: On<Pointer<Click>>, mut game_state: ResMut<GameState>) {
+    if !can_buy_dash_cooldown(&game_state) {
+        return;
+    }
+    game_state.dash.cooldown_seconds -= 0.25;
+    game_state.spent += 45;
+}
+
+impl PlayerUpgrade {
+    fn name(self) -> &'static str {
+        match self {
+            PlayerUpgrade::MaxHealth => "Max Health",
+            PlayerUpgrade::Regeneration => "Regeneration",
+            PlayerUpgrade::MoveSpeed => "Move Speed",
+            PlayerUpgrade::Resistance => "Resistance",
+        }
+    }
+
+    fn label(self, game_state: &GameState) -> String {
+        let stats = &game_state.player;
+        match self {
+            PlayerUpgrade::MaxHealth => format!("Max Health: {:.0}", stats.max_health),
+            PlayerUpgrade::Regeneration => {
+                format!("Regeneration: {:.1}/s", stats.regen_per_second)
+            }
+            PlayerUpgrade::MoveSpeed => format!("Move Speed: {:.2}", stats.move_speed),
+            PlayerUpgrade::Resistance => {
+                format!("Resistance: {:.0}%", stats.damage_resistance * 100.0)
+            }
+        }
+    }
+
+    fn is_maxed(self, game_state: &GameState) -> bool {
+        let stats = &game_state.player;
+        match self {
+            PlayerUpgrade::MaxHealth => stats.max_health >= 200.0,
+            PlayerUpgrade::Regeneration => stats.regen_per_second >= 3.0,
+            PlayerUpgrade::MoveSpeed => stats.move_speed >= 5.0,
+            // Capped at 50% so the horde always stays dangerous. Tenths don't add up exactly
+            // in floating point, hence the slack.
+            PlayerUpgrade::Resistance => stats.damage_resistance >= 0.49,
+        }
+    }
+
+    fn apply(self, game_state: &mut GameState) {
+        let stats = &mut game_state.player;
+        match self {
+            PlayerUpgrade::MaxHealth => stats.max_health += 20.0,
+            PlayerUpgrade::Regeneration => stats.regen_per_second += 0.5,
+            PlayerUpgrade::MoveSpeed => stats.move_speed += 0.25,
+            PlayerUpgrade::Resistance => stats.damage_resistance += 0.1,
+        }
+    }
+}
+
+fn player_upgrade_row(upgrade: PlayerUpgrade) -> impl Bundle {
+    (
+        Name::new(upgrade.name()),
+        Node {
+            flex_direction: FlexDirection::Row,
+            column_gap: px(10),
+            ..default()
+        },
+        Pickable::IGNORE,
+        Visibility::default(),
+        children![
+            (widget::label(""), PlayerUpgradeText(upgrade)),
+            (
+                widget::button_small(
+                    "+",
+                    move |_: On<Pointer<Click>>, mut game_state: ResMut<GameState>| {
+                        if !can_buy_player_upgrade(&game_state, upgrade) {
+                            return;
+                        }
+                        upgrade.apply(&mut game_state);
+                        game_state.spent += PLAYER_UPGRADE_COST;
+                    },
+                ),
+                PlayerUpgradeButton(upgrade),
+            ),
+        ],
+    )
+}
+
+fn can_buy_player_upgrade(game_state: &GameState, upgrade: PlayerUpgrade) -> bool {
+    !upgrade.is_maxed(game_state) && game_state.gold - game_state.spent >= PLAYER_UPGRADE_COST
+}
+
+fn can_buy_last_stand(game_state: &GameState) -> bool {
+    !game_state.last_stand && game_state.gold - game_state.spent >= LAST_STAND_COST
+}
+
+fn buy_last_stand(_: On<Pointer<Click>>, mut game_state: ResMut<GameState>) {
+    if !can_buy_last_stand(&game_state) {
+        return;
+    }
+    game_state.last_stand = true;
+    game_state.spent += LAST_STAND_COST;
+}
+
 fn can_buy_torch(game_state: &GameState) -> bool {
-    game_state.torch.is_none() && game_state.total_kills - game_state.spent >= 100
+    game_state.torches.len() < MAX_TORCHES && game_state.gold - game_state.spent >= 100
 }
 
-fn buy_torch(_: On<Pointer<Click>>, mut game_state: ResMut<GameState>) {
+fn buy_torch(
+    _: On<Pointer<Click>>,
+    mut game_state: ResMut<GameState>,
+    mut selected: ResMut<SelectedTorch>,
+    assets: Res<GameAssets>,
+    levels: Res<Assets<LevelFile>>,
+) {
     if !can_buy_torch(&game_state) {
         return;
     }
-    game_state.torch = Some(Torch {
+    let level = assets.level(&levels);
+    let slot = game_state.torches.len();
+    // Past the level's torch slots, fan new torches out around the player's start so they
+    // don't stack up.
+    let position = level.torch_slots.get(slot).copied().unwrap_or_else(|| {
+        let angle = slot as f32 * std::f32::consts::FRAC_PI_2;
+        level.player_start.with_y(0.0) + Quat::from_rotation_y(angle) * vec3(3.0, 0.0, 3.0)
+    });
+    game_state.torches.push(Torch {
         range: 5.0,
         on_seconds: 2.,
         off_seconds: 2.,
+        position,
+        kind: TorchKind::Automatic,
     });
     game_state.spent += 100;
+    selected.0 = game_state.torches.len() - 1;
 }
 
-fn max_torch_range(game_state: &GameState) -> bool {
-    game_state.torch.as_ref().is_some_and(|t| t.range >= 10.0)
+fn max_torch_range(torch: &Torch) -> bool {
+    torch.range >= 10.0
 }
 
-fn can_buy_torch_range(game_state: &GameState) -> bool {
-    game_state.torch.is_some()
-        && game_state.total_kills - game_state.spent >= 45
-        && !max_torch_range(game_state)
+fn can_buy_torch_range(game_state: &GameState, selected: usize) -> bool {
+    game_state.gold - game_state.spent >= 45
+        && game_state
+            .torches
+            .get(selected)
+            .is_some_and(|t| !max_torch_range(t))
 }
 
-fn increase_torch_range(_: On<Pointer<Click>>, mut game_state: ResMut<GameState>) {
-    if !can_buy_torch_range(&game_state) {
+fn increase_torch_range(
+    _: On<Pointer<Click>>,
+    mut game_state: ResMut<GameState>,
+    selected: Res<SelectedTorch>,
+) {
+    if !can_buy_torch_range(&game_state, selected.0) {
         return;
     }
-    game_state.torch.as_mut().unwrap().range += 1.0;
+    game_state.torches[selected.0].range += 1.0;
     game_state.spent += 45;
 }
 
-fn can_buy_torch_on_seconds(game_state: &GameState) -> bool {
-    game_state.torch.is_some() && game_state.total_kills - game_state.spent >= 45
+fn can_buy_torch_on_seconds(game_state: &GameState, selected: usize) -> bool {
+    selected < game_state.torches.len() && game_state.gold - game_state.spent >= 45
 }
 
-fn increase_torch_on_seconds(_: On<Pointer<Click>>, mut game_state: ResMut<GameState>) {
-    if !can_buy_torch_on_seconds(&game_state) {
+fn increase_torch_on_seconds(
+    _: On<Pointer<Click>>,
+    mut game_state: ResMut<GameState>,
+    selected: Res<SelectedTorch>,
+) {
+    if !can_buy_torch_on_seconds(&game_state, selected.0) {
         return;
     }
-    game_state.torch.as_mut().unwrap().on_seconds += 1.;
+    game_state.torches[selected.0].on_seconds += 1.;
     game_state.spent += 45;
 }
 
-fn can_buy_torch_off_seconds(game_state: &GameState) -> bool {
-    game_state.torch.is_some()
-        && game_state.total_kills - game_state.spent >= 45
-        && !max_torch_off_seconds(game_state)
+fn can_buy_torch_off_seconds(game_state: &GameState, selected: usize) -> bool {
+    game_state.gold - game_state.spent >= 45
+        && game_state
+            .torches
+            .get(selected)
+            .is_some_and(|t| !max_torch_off_seconds(t))
 }
 
-fn max_torch_off_seconds(game_state: &GameState) -> bool {
-    game_state
-        .torch
-        .as_ref()
-        .is_some_and(|t| t.off_seconds <= 0.3)
+fn max_torch_off_seconds(torch: &Torch) -> bool {
+    torch.off_seconds <= 0.3
 }
 
-fn decrease_torch_off_seconds(_: On<Pointer<Click>>, mut game_state: ResMut<GameState>) {
-    if !can_buy_torch_off_seconds(&game_state) {
+fn decrease_torch_off_seconds(
+    _: On<Pointer<Click>>,
+    mut game_state: ResMut<GameState>,
+    selected: Res<SelectedTorch>,
+) {
+    if !can_buy_torch_off_seconds(&game_state, selected.0) {
         return;
     }
-    game_state.torch.as_mut().unwrap().off_seconds -= 0.1;
+    game_state.torches[selected.0].off_seconds -= 0.1;
     game_state.spent += 45;
 }
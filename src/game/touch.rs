@@ -0,0 +1,232 @@
+//! On-screen controls for touch screens: a floating joystick on the left half of the screen
+//! and a drag-to-aim zone on the right. They show up once the player touches the screen.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_enhanced_input::prelude::*;
+
+use crate::{
+    PausableSystems,
+    game::{
+        GameStateMachine,
+        level::{Aim, Movement},
+    },
+    screens::{Screen, pause_on_click},
+    theme::widget,
+};
+
+/// How far (in logical pixels) the thumb has to move from where it landed for full speed.
+const JOYSTICK_RADIUS: f32 = 60.0;
+const JOYSTICK_KNOB_RADIUS: f32 = 24.0;
+/// Where the joystick rests while it isn't held, measured from the bottom-left corner.
+const JOYSTICK_MARGIN: f32 = 40.0;
+/// Drags shorter than this don't move the aim, so a tap doesn't snap the beam around.
+const AIM_DEAD_ZONE: f32 = 12.0;
+
+const JOYSTICK_BASE_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.15);
+const JOYSTICK_KNOB_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TouchControls>();
+    app.add_systems(OnEnter(GameStateMachine::Level), spawn_touch_controls);
+    app.add_systems(Update, detect_touch_input);
+    app.add_systems(
+        Update,
+        (assign_touches, feed_touch_actions, update_joystick)
+            .chain()
+            .run_if(in_state(GameStateMachine::Level))
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        show_touch_controls.run_if(in_state(GameStateMachine::Level)),
+    );
+}
+
+#[derive(Resource, Default)]
+struct TouchControls {
+    /// Set by the first touch, cleared again when the player goes back to the keyboard.
+    visible: bool,
+    /// The touch steering the joystick.
+    movement_touch: Option<u64>,
+    /// The touch dragging the aim.
+    aim_touch: Option<u64>,
+}
+
+#[derive(Component)]
+struct TouchControlsRoot;
+
+#[derive(Component)]
+struct JoystickBase;
+
+#[derive(Component)]
+struct JoystickKnob;
+
+fn spawn_touch_controls(mut commands: Commands) {
+    commands.spawn((
+        GlobalZIndex(1),
+        DespawnOnExit(GameStateMachine::Level),
+        DespawnOnExit(Screen::Gameplay),
+        Name::new("Touch Controls"),
+        TouchControlsRoot,
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            height: percent(100),
+            ..default()
+        },
+        Visibility::Hidden,
+        Pickable::IGNORE,
+        children![
+            (
+                Name::new("Joystick"),
+                JoystickBase,
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: px(JOYSTICK_RADIUS * 2.0),
+                    height: px(JOYSTICK_RADIUS * 2.0),
+                    border_radius: BorderRadius::MAX,
+                    ..default()
+                },
+                BackgroundColor(JOYSTICK_BASE_COLOR),
+                Pickable::IGNORE,
+                children![(
+                    Name::new("Joystick Knob"),
+                    JoystickKnob,
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: px(JOYSTICK_KNOB_RADIUS * 2.0),
+                        height: px(JOYSTICK_KNOB_RADIUS * 2.0),
+                        border_radius: BorderRadius::MAX,
+                        ..default()
+                    },
+                    BackgroundColor(JOYSTICK_KNOB_COLOR),
+                    Pickable::IGNORE,
+                )],
+            ),
+            (
+                Name::new("Pause Button"),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: px(20),
+                    right: px(20),
+                    ..default()
+                },
+                children![widget::button_small("||", pause_on_click)],
+            ),
+        ],
+    ));
+}
+
+fn detect_touch_input(
+    touches: Res<Touches>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut controls: ResMut<TouchControls>,
+) {
+    if touches.any_just_pressed() {
+        controls.visible = true;
+    } else if keys.get_just_pressed().next().is_some() {
+        controls.visible = false;
+    }
+}
+
+fn show_touch_controls(
+    controls: Res<TouchControls>,
+    mut root: Single<&mut Visibility, With<TouchControlsRoot>>,
+) {
+    **root = if controls.visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
+
+/// New touches on the left half steer, new touches on the right half aim.
+fn assign_touches(
+    touches: Res<Touches>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut controls: ResMut<TouchControls>,
+) {
+    // Forget lifted fingers, including any lifted while the game was paused.
+    controls.movement_touch = controls
+        .movement_touch
+        .filter(|id| touches.get_pressed(*id).is_some());
+    controls.aim_touch = controls
+        .aim_touch
+        .filter(|id| touches.get_pressed(*id).is_some());
+
+    for touch in touches.iter_just_pressed() {
+        if touch.start_position().x < window.width() / 2.0 {
+            controls.movement_touch.get_or_insert(touch.id());
+        } else {
+            controls.aim_touch.get_or_insert(touch.id());
+        }
+    }
+}
+
+/// How far a touch has been dragged, with up as +Y to match the stick actions.
+fn drag(touch: &Touch) -> Vec2 {
+    let delta = touch.position() - touch.start_position();
+    vec2(delta.x, -delta.y)
+}
+
+/// Drives the same actions as the keyboard and gamepad, so cursed controls, dashing and the
+/// rest of the movement code treat touch input no differently.
+fn feed_touch_actions(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    controls: Res<TouchControls>,
+    movement: Single<Entity, With<Action<Movement>>>,
+    aim: Single<Entity, With<Action<Aim>>>,
+) {
+    if let Some(touch) = controls
+        .movement_touch
+        .and_then(|id| touches.get_pressed(id))
+    {
+        let input = (drag(touch) / JOYSTICK_RADIUS).clamp_length_max(1.0);
+        if input != Vec2::ZERO {
+            commands
+                .entity(*movement)
+                .insert(ActionMock::once(ActionState::Fired, input));
+        }
+    }
+
+    if let Some(touch) = controls.aim_touch.and_then(|id| touches.get_pressed(id)) {
+        let input = drag(touch);
+        if input.length() > AIM_DEAD_ZONE {
+            commands
+                .entity(*aim)
+                .insert(ActionMock::once(ActionState::Fired, input.normalize()));
+        }
+    }
+}
+
+/// Centers the joystick under the thumb while it's held, and parks it in the corner otherwise.
+fn update_joystick(
+    touches: Res<Touches>,
+    controls: Res<TouchControls>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut base: Single<&mut Node, With<JoystickBase>>,
+    mut knob: Single<&mut Node, (With<JoystickKnob>, Without<JoystickBase>)>,
+) {
+    let touch = controls
+        .movement_touch
+        .and_then(|id| touches.get_pressed(id));
+    let (center, offset) = match touch {
+        Some(touch) => (
+            touch.start_position(),
+            (touch.position() - touch.start_position()).clamp_length_max(JOYSTICK_RADIUS),
+        ),
+        None => (
+            vec2(
+                JOYSTICK_MARGIN + JOYSTICK_RADIUS,
+                window.height() - JOYSTICK_MARGIN - JOYSTICK_RADIUS,
+            ),
+            Vec2::ZERO,
+        ),
+    };
+
+    base.left = px(center.x - JOYSTICK_RADIUS);
+    base.top = px(center.y - JOYSTICK_RADIUS);
+    knob.left = px(JOYSTICK_RADIUS - JOYSTICK_KNOB_RADIUS + offset.x);
+    knob.top = px(JOYSTICK_RADIUS - JOYSTICK_KNOB_RADIUS + offset.y);
+}
@@ -0,0 +1,300 @@
+//! Player-configurable key and gamepad bindings, saved to disk between sessions.
+
+use bevy::{
+    input::keyboard::NativeKeyCode, platform::collections::HashMap, prelude::*, reflect::Enum,
+};
+use bevy_enhanced_input::prelude::Binding;
+#[cfg(not(target_family = "wasm"))]
+use {
+    bevy::reflect::{DynamicEnum, DynamicVariant},
+    serde::{Deserialize, Serialize},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Keybindings::load());
+    app.add_systems(
+        Update,
+        save_keybindings
+            .run_if(resource_changed::<Keybindings>.and(not(resource_added::<Keybindings>))),
+    );
+}
+
+/// Every action the player can rebind, in the order the controls menu lists them.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    AimUp,
+    AimDown,
+    AimLeft,
+    AimRight,
+    IgniteTorch,
+    CycleLens,
+    ToggleBeamFocus,
+    Dash,
+    Sprint,
+}
+
+impl GameAction {
+    pub const ALL: [GameAction; 13] = [
+        GameAction::MoveUp,
+        GameAction::MoveDown,
+        GameAction::MoveLeft,
+        GameAction::MoveRight,
+        GameAction::AimUp,
+        GameAction::AimDown,
+        GameAction::AimLeft,
+        GameAction::AimRight,
+        GameAction::IgniteTorch,
+        GameAction::CycleLens,
+        GameAction::ToggleBeamFocus,
+        GameAction::Dash,
+        GameAction::Sprint,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameAction::MoveUp => "Move Up",
+            GameAction::MoveDown => "Move Down",
+            GameAction::MoveLeft => "Move Left",
+            GameAction::MoveRight => "Move Right",
+            GameAction::AimUp => "Aim Up",
+            GameAction::AimDown => "Aim Down",
+            GameAction::AimLeft => "Aim Left",
+            GameAction::AimRight => "Aim Right",
+            GameAction::IgniteTorch => "Ignite Torch",
+            GameAction::CycleLens => "Swap Lens",
+            GameAction::ToggleBeamFocus => "Beam Focus",
+            GameAction::Dash => "Dash",
+            GameAction::Sprint => "Sprint",
+        }
+    }
+
+    /// Movement and aim directions are covered by the sticks, so they only take keys.
+    pub fn fixed_gamepad_binding(self) -> Option<&'static str> {
+        match self {
+            GameAction::MoveUp
+            | GameAction::MoveDown
+            | GameAction::MoveLeft
+            | GameAction::MoveRight => Some("Left Stick"),
+            GameAction::AimUp
+            | GameAction::AimDown
+            | GameAction::AimLeft
+            | GameAction::AimRight => Some("Right Stick"),
+            _ => None,
+        }
+    }
+}
+
+/// Which half of a binding is being talked about.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputDevice {
+    Keyboard,
+    Gamepad,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ActionBinding {
+    pub key: Option<KeyCode>,
+    pub button: Option<GamepadButton>,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Keybindings(HashMap<GameAction, ActionBinding>);
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let binding = |key, button| ActionBinding {
+            key: Some(key),
+            button,
+        };
+        Self(HashMap::from_iter([
+            (GameAction::MoveUp, binding(KeyCode::KeyW, None)),
+            (GameAction::MoveDown, binding(KeyCode::KeyS, None)),
+            (GameAction::MoveLeft, binding(KeyCode::KeyA, None)),
+            (GameAction::MoveRight, binding(KeyCode::KeyD, None)),
+            (GameAction::AimUp, binding(KeyCode::ArrowUp, None)),
+            (GameAction::AimDown, binding(KeyCode::ArrowDown, None)),
+            (GameAction::AimLeft, binding(KeyCode::ArrowLeft, None)),
+            (GameAction::AimRight, binding(KeyCode::ArrowRight, None)),
+            (
+                GameAction::IgniteTorch,
+                binding(KeyCode::KeyE, Some(GamepadButton::West)),
+            ),
+            (
+                GameAction::CycleLens,
+                binding(KeyCode::KeyR, Some(GamepadButton::North)),
+            ),
+            (
+                GameAction::ToggleBeamFocus,
+                binding(KeyCode::KeyF, Some(GamepadButton::RightTrigger)),
+            ),
+            (
+                GameAction::Dash,
+                binding(KeyCode::Space, Some(GamepadButton::South)),
+            ),
+            (
+                GameAction::Sprint,
+                binding(KeyCode::ShiftLeft, Some(GamepadButton::LeftThumb)),
+            ),
+        ]))
+    }
+}
+
+impl Keybindings {
+    pub fn get(&self, action: GameAction) -> ActionBinding {
+        self.0.get(&action).copied().unwrap_or_default()
+    }
+
+    /// Every input `action` is bound to, keyboard first, ready to spawn into its action.
+    pub fn bindings(&self, action: GameAction) -> Vec<Binding> {
+        let binding = self.get(action);
+        binding
+            .key
+            .map(Into::into)
+            .into_iter()
+            .chain(binding.button.map(Into::into))
+            .collect()
+    }
+
+    /// The key for a movement or aim direction. These always have one, so an unbound
+    /// direction falls back to its default.
+    pub fn direction_key(&self, action: GameAction) -> KeyCode {
+        self.get(action)
+            .key
+            .or_else(|| Self::default().get(action).key)
+            .unwrap_or(KeyCode::Unidentified(NativeKeyCode::Unidentified))
+    }
+
+    /// Binds `key` to `action`. If another action already used it, the two trade places and
+    /// the other action is returned.
+    pub fn bind_key(&mut self, action: GameAction, key: KeyCode) -> Option<GameAction> {
+        let previous = self.get(action).key;
+        let conflict = GameAction::ALL
+            .into_iter()
+            .find(|&other| other != action && self.get(other).key == Some(key));
+        if let Some(other) = conflict {
+            self.0.entry(other).or_default().key = previous;
+        }
+        self.0.entry(action).or_default().key = Some(key);
+        conflict
+    }
+
+    /// Binds `button` to `action`, swapping with any action that already used it.
+    pub fn bind_button(&mut self, action: GameAction, button: GamepadButton) -> Option<GameAction> {
+        let previous = self.get(action).button;
+        let conflict = GameAction::ALL
+            .into_iter()
+            .find(|&other| other != action && self.get(other).button == Some(button));
+        if let Some(other) = conflict {
+            self.0.entry(other).or_default().button = previous;
+        }
+        self.0.entry(action).or_default().button = Some(button);
+        conflict
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn to_saved(&self) -> Vec<SavedBinding> {
+        GameAction::ALL
+            .into_iter()
+            .map(|action| {
+                let binding = self.get(action);
+                SavedBinding {
+                    action: format!("{action:?}"),
+                    key: binding.key.map(|key| key.variant_name().to_string()),
+                    button: binding
+                        .button
+                        .map(|button| button.variant_name().to_string()),
+                }
+            })
+            .collect()
+    }
+
+    /// Starts from the defaults so actions added since the file was written still get a binding.
+    #[cfg(not(target_family = "wasm"))]
+    fn from_saved(saved: Vec<SavedBinding>) -> Self {
+        let mut bindings = Self::default();
+        for entry in saved {
+            // Skip actions that have since been removed from the game.
+            let Some(action) = GameAction::ALL
+                .into_iter()
+                .find(|action| format!("{action:?}") == entry.action)
+            else {
+                continue;
+            };
+            let binding = bindings.0.entry(action).or_default();
+            binding.key = entry.key.as_deref().and_then(unit_variant);
+            binding.button = entry.button.as_deref().and_then(unit_variant);
+        }
+        bindings
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn load() -> Self {
+        let Ok(contents) = std::fs::read_to_string(KEYBINDINGS_PATH) else {
+            return Self::default();
+        };
+        match ron::from_str(&contents) {
+            Ok(saved) => Self::from_saved(saved),
+            Err(err) => {
+                warn!("Ignoring unreadable {KEYBINDINGS_PATH}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    /// There's no file system on the web, so bindings only last for the session.
+    #[cfg(target_family = "wasm")]
+    fn load() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+const KEYBINDINGS_PATH: &str = "keybindings.ron";
+
+/// On-disk form of a binding. Actions, keys and buttons are stored by variant name.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Serialize, Deserialize)]
+struct SavedBinding {
+    action: String,
+    key: Option<String>,
+    button: Option<String>,
+}
+
+/// Rebuilds a field-less enum variant, such as a [`KeyCode`], from its name.
+#[cfg(not(target_family = "wasm"))]
+fn unit_variant<T: FromReflect>(name: &str) -> Option<T> {
+    T::from_reflect(&DynamicEnum::new(name, DynamicVariant::Unit))
+}
+
+/// A short, readable name for a key, e.g. `W` rather than `KeyW`.
+pub fn key_name(key: KeyCode) -> String {
+    let name = key.variant_name();
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(name)
+        .to_string()
+}
+
+pub fn button_name(button: GamepadButton) -> String {
+    button.variant_name().to_string()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_keybindings(bindings: Res<Keybindings>) {
+    let saved = bindings.to_saved();
+    let result = ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            std::fs::write(KEYBINDINGS_PATH, contents).map_err(|err| err.to_string())
+        });
+    if let Err(err) = result {
+        warn!("Couldn't save {KEYBINDINGS_PATH}: {err}");
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn save_keybindings() {}
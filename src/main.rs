@@ -8,12 +8,18 @@ mod crt_postprocess;
 #[cfg(feature = "dev")]
 mod dev_tools;
 mod game;
+mod keybindings;
 mod menus;
 mod quotes;
 mod screens;
 mod theme;
 
-use bevy::{asset::AssetMetaCheck, camera::ScalingMode, prelude::*};
+use bevy::{
+    asset::AssetMetaCheck,
+    camera::ScalingMode,
+    picking::mesh_picking::{MeshPickingCamera, MeshPickingPlugin, MeshPickingSettings},
+    prelude::*,
+};
 use bevy_rand::{plugin::EntropyPlugin, prelude::WyRand};
 use bevy_rapier3d::prelude::*;
 use bevy_seedling::SeedlingPlugin;
@@ -22,7 +28,7 @@ use bevy_vox::VoxPlugin;
 
 use crate::{
     crt_postprocess::{CrtPostProcessPlugin, CrtSettings},
-    game::LIGHT_COLOR,
+    game::{AMBIENT_BRIGHTNESS, LIGHT_COLOR},
 };
 
 fn main() -> AppExit {
@@ -72,6 +78,12 @@ impl Plugin for AppPlugin {
         );
         app.add_plugins(EntropyPlugin::<WyRand>::default());
 
+        // Only entities marked `Pickable` are ray cast against, so the level's meshes stay cheap.
+        app.add_plugins(MeshPickingPlugin);
+        app.insert_resource(MeshPickingSettings {
+            require_markers: true,
+            ..default()
+        });
         app.add_plugins(Sprite3dPlugin);
         app.add_plugins(CrtPostProcessPlugin);
         app.add_plugins(VoxPlugin { swap_yz: true });
@@ -80,6 +92,7 @@ impl Plugin for AppPlugin {
             asset_tracking::plugin,
             #[cfg(feature = "dev")]
             dev_tools::plugin,
+            keybindings::plugin,
             menus::plugin,
             screens::plugin,
             theme::plugin,
@@ -95,6 +108,11 @@ impl Plugin for AppPlugin {
 #[derive(States, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 struct Pause(pub bool);
 
+/// Keeps the game paused when the pause menu closes. Held by the level editor while it's open.
+#[derive(Resource)]
+#[cfg_attr(not(feature = "dev"), allow(dead_code))]
+struct PauseHeld;
+
 /// A system set for systems that shouldn't run while the game is paused.
 #[derive(SystemSet, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 struct PausableSystems;
@@ -120,9 +138,10 @@ fn spawn_camera(mut commands: Commands) {
             ..default()
         },
         IsometricCamera { offset },
+        MeshPickingCamera,
         AmbientLight {
             color: LIGHT_COLOR,
-            brightness: 35.0,
+            brightness: AMBIENT_BRIGHTNESS,
             ..default()
         },
         Projection::from(OrthographicProjection {
@@ -0,0 +1,224 @@
+//! The controls menu, for rebinding keyboard keys and gamepad buttons.
+
+use bevy::{ecs::spawn::SpawnWith, input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{
+    keybindings::{GameAction, InputDevice, Keybindings, button_name, key_name},
+    menus::{
+        Menu,
+        settings::{btn, text},
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<BindingCapture>();
+    app.add_systems(OnEnter(Menu::Controls), spawn_controls_menu);
+    app.add_systems(OnExit(Menu::Controls), cancel_capture);
+    app.add_systems(
+        Update,
+        (
+            go_back.run_if(input_just_pressed(KeyCode::Escape).and(not_capturing)),
+            capture_binding,
+            update_binding_labels,
+        )
+            .chain()
+            .run_if(in_state(Menu::Controls)),
+    );
+}
+
+/// The binding waiting for the player to press something, if any.
+#[derive(Resource, Default)]
+struct BindingCapture(Option<(GameAction, InputDevice)>);
+
+/// Shows what `action` is bound to on `device`.
+#[derive(Component)]
+struct BindingLabel {
+    action: GameAction,
+    device: InputDevice,
+}
+
+/// Prompts while capturing, and reports swapped bindings afterwards.
+#[derive(Component)]
+struct ControlsStatus;
+
+fn spawn_controls_menu(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(Menu::Controls),
+        GlobalZIndex(2),
+        BackgroundColor(Color::srgb(0.23, 0.23, 0.23)),
+        Node {
+            width: Val::Percent(80.0),
+            height: Val::Percent(90.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Vh(2.0),
+            margin: UiRect::AUTO,
+            padding: UiRect::axes(Val::Px(50.0), Val::Px(20.0)),
+            border: UiRect::axes(Val::Px(2.0), Val::Px(2.0)),
+            border_radius: BorderRadius::all(Val::Px(25.0)),
+            ..default()
+        },
+        BorderColor::all(Color::srgb(0.9, 0.9, 0.9)),
+        children![
+            text((
+                Text::new("Controls"),
+                TextFont {
+                    font_size: 32.0,
+                    ..Default::default()
+                },
+            )),
+            bindings_grid(),
+            (
+                Text::new(""),
+                TextFont::from_font_size(18.0),
+                ControlsStatus
+            ),
+            btn("Back", go_back_on_click),
+        ],
+    ));
+}
+
+fn bindings_grid() -> impl Bundle {
+    (
+        Name::new("Bindings Grid"),
+        Node {
+            row_gap: Val::Px(4.0),
+            column_gap: Val::Px(12.0),
+            display: Display::Grid,
+            align_items: AlignItems::Center,
+            grid_template_columns: vec![
+                GridTrack::flex(1.0),
+                GridTrack::px(140.0),
+                GridTrack::auto(),
+                GridTrack::px(140.0),
+                GridTrack::auto(),
+            ],
+            ..default()
+        },
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            for action in GameAction::ALL {
+                parent.spawn((Text::new(action.name()), TextFont::from_font_size(18.0)));
+                parent.spawn(binding_label(action, InputDevice::Keyboard));
+                parent.spawn(set_button(action, InputDevice::Keyboard));
+                if let Some(stick) = action.fixed_gamepad_binding() {
+                    parent.spawn(text((Text::new(stick), TextFont::from_font_size(16.0))));
+                    parent.spawn(Node::default());
+                } else {
+                    parent.spawn(binding_label(action, InputDevice::Gamepad));
+                    parent.spawn(set_button(action, InputDevice::Gamepad));
+                }
+            }
+        })),
+    )
+}
+
+fn binding_label(action: GameAction, device: InputDevice) -> impl Bundle {
+    text((
+        Text::new(""),
+        TextFont::from_font_size(16.0),
+        BindingLabel { action, device },
+    ))
+}
+
+fn set_button(action: GameAction, device: InputDevice) -> impl Bundle {
+    btn(
+        "Set",
+        move |_: On<Pointer<Click>>, mut capture: ResMut<BindingCapture>| {
+            capture.0 = Some((action, device));
+        },
+    )
+}
+
+fn not_capturing(capture: Res<BindingCapture>) -> bool {
+    capture.0.is_none()
+}
+
+/// Binds the next key or gamepad button pressed. Escape cancels, so it can't be bound.
+fn capture_binding(
+    mut capture: ResMut<BindingCapture>,
+    mut keybindings: ResMut<Keybindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut status: Single<&mut Text, With<ControlsStatus>>,
+) {
+    let Some((action, device)) = capture.0 else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        capture.0 = None;
+        status.0.clear();
+        return;
+    }
+
+    let swapped = match device {
+        InputDevice::Keyboard => {
+            let Some(&key) = keys.get_just_pressed().next() else {
+                return;
+            };
+            keybindings.bind_key(action, key)
+        }
+        InputDevice::Gamepad => {
+            let Some(&button) = gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next())
+            else {
+                return;
+            };
+            keybindings.bind_button(action, button)
+        }
+    };
+
+    capture.0 = None;
+    status.0 = match swapped {
+        Some(other) => format!("Swapped with {}", other.name()),
+        None => String::new(),
+    };
+}
+
+fn update_binding_labels(
+    capture: Res<BindingCapture>,
+    keybindings: Res<Keybindings>,
+    mut labels: Query<(&mut Text, &BindingLabel)>,
+    new_labels: Query<(), Added<BindingLabel>>,
+    mut status: Single<&mut Text, (With<ControlsStatus>, Without<BindingLabel>)>,
+) {
+    if !capture.is_changed() && !keybindings.is_changed() && new_labels.is_empty() {
+        return;
+    }
+
+    for (mut text, label) in &mut labels {
+        let binding = keybindings.get(label.action);
+        text.0 = if capture.0 == Some((label.action, label.device)) {
+            "...".to_string()
+        } else {
+            match label.device {
+                InputDevice::Keyboard => binding.key.map(key_name),
+                InputDevice::Gamepad => binding.button.map(button_name),
+            }
+            .unwrap_or_else(|| "-".to_string())
+        };
+    }
+
+    if let Some((action, device)) = capture.0 {
+        let input = match device {
+            InputDevice::Keyboard => "a key",
+            InputDevice::Gamepad => "a gamepad button",
+        };
+        status.0 = format!("Press {input} for {} (Esc to cancel)", action.name());
+    }
+}
+
+fn cancel_capture(mut capture: ResMut<BindingCapture>) {
+    capture.0 = None;
+}
+
+fn go_back_on_click(_: On<Pointer<Click>>, next_menu: ResMut<NextState<Menu>>) {
+    go_back(next_menu);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Settings);
+}
@@ -1,5 +1,6 @@
 //! The game's menus and transitions between them.
 
+mod controls;
 mod credits;
 mod main;
 mod pause;
@@ -11,6 +12,7 @@ pub(super) fn plugin(app: &mut App) {
     app.init_state::<Menu>();
 
     app.add_plugins((
+        controls::plugin,
         credits::plugin,
         main::plugin,
         settings::plugin,
@@ -25,5 +27,6 @@ pub enum Menu {
     Main,
     Credits,
     Settings,
+    Controls,
     Pause,
 }
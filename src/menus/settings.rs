@@ -243,7 +243,10 @@ fn back_button() -> impl Bundle {
             width: Val::Percent(100.0),
             ..Default::default()
         },
-        children![btn("Back", go_back_on_click),],
+        children![
+            btn("Controls", open_controls),
+            btn("Back", go_back_on_click)
+        ],
     )
 }
 
@@ -376,6 +379,10 @@ fn button_hover(
     }
 }
 
+fn open_controls(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Controls);
+}
+
 fn go_back_on_click(
     _: On<Pointer<Click>>,
     screen: Res<State<Screen>>,
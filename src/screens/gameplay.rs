@@ -2,7 +2,7 @@
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 
-use crate::{Pause, game::start, menus::Menu, screens::Screen};
+use crate::{Pause, PauseHeld, game::start, menus::Menu, screens::Screen};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Gameplay), start);
@@ -19,7 +19,7 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnExit(Screen::Gameplay), (close_menu, unpause));
     app.add_systems(
         OnEnter(Menu::None),
-        unpause.run_if(in_state(Screen::Gameplay)),
+        unpause.run_if(in_state(Screen::Gameplay).and(not(resource_exists::<PauseHeld>))),
     );
 }
 
@@ -45,6 +45,21 @@ fn spawn_pause_overlay(mut commands: Commands) {
     ));
 }
 
+/// Pauses from an on-screen button, for players without an Escape key.
+pub fn pause_on_click(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    pause_state: Res<State<Pause>>,
+) {
+    // The button stays on screen under the pause menu.
+    if pause_state.get().0 {
+        return;
+    }
+    commands.run_system_cached(pause);
+    commands.run_system_cached(spawn_pause_overlay);
+    commands.run_system_cached(open_pause_menu);
+}
+
 fn open_pause_menu(mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Pause);
 }
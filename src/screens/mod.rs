@@ -6,6 +6,8 @@ mod title;
 
 use bevy::prelude::*;
 
+pub use gameplay::pause_on_click;
+
 pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
 
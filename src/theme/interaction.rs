@@ -37,7 +37,13 @@ fn apply_interaction_palette_on_release(
         return;
     };
 
-    *bg = palette.hovered.into();
+    // A lifted finger isn't hovering anything, so don't leave tapped buttons highlighted.
+    *bg = if click.pointer_id.is_touch() {
+        palette.none
+    } else {
+        palette.hovered
+    }
+    .into();
 }
 
 fn apply_interaction_palette_on_over(